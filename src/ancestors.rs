@@ -0,0 +1,90 @@
+use crate::{NodeIndex, SceneGraph};
+
+/// An iterator over the ancestors of a node in a [SceneGraph], from its parent up to the root.
+/// See [SceneGraph::ancestors] for more information.
+pub struct SceneGraphAncestors<'a, T> {
+    sg: &'a SceneGraph<T>,
+    current_node: Option<NodeIndex>,
+}
+
+impl<'a, T> SceneGraphAncestors<'a, T> {
+    pub(crate) fn new(sg: &'a SceneGraph<T>, node_index: NodeIndex) -> Self {
+        SceneGraphAncestors {
+            sg,
+            current_node: sg.parent(node_index),
+        }
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphAncestors<'a, T> {
+    type Item = (NodeIndex, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node_index = self.current_node?;
+
+        let value = match node_index {
+            NodeIndex::Root => {
+                self.current_node = None;
+                &self.sg.root
+            }
+            NodeIndex::Branch(idx) => {
+                let node = &self.sg.arena[idx];
+                self.current_node = Some(node.parent);
+                &node.value
+            }
+        };
+
+        Some((node_index, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_nothing_on_empty_iteration() {
+        let scene_graph = SceneGraph::new("Root");
+
+        assert!(scene_graph.ancestors(NodeIndex::Root).next().is_none());
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let first_child = sg.attach(root_idx, "First Child").unwrap();
+        let grandchild = sg.attach(first_child, "First Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "First Greatgrandchild").unwrap();
+        sg.attach(root_idx, "Second Child").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.ancestors(greatgrandchild)),
+            vec![
+                (grandchild, &"First Grandchild"),
+                (first_child, &"First Child"),
+                (root_idx, &"Root")
+            ]
+        );
+    }
+
+    #[test]
+    fn child_of_root_yields_root_once() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+
+        let mut ancestors = sg.ancestors(child);
+        assert_eq!(ancestors.next(), Some((NodeIndex::Root, &"Root")));
+        assert_eq!(ancestors.next(), None);
+        assert_eq!(ancestors.next(), None);
+    }
+
+    #[test]
+    fn removed_node_yields_nothing() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        sg.remove(child);
+
+        assert!(sg.ancestors(child).next().is_none());
+    }
+}
@@ -69,4 +69,45 @@ mod tests {
             vec!["First Grandchild", "Second Grandchild", "Third Grandchild"]
         );
     }
+
+    #[test]
+    fn children_yields_indices_without_descending() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let first_child = sg.attach(root_idx, "First Child").unwrap();
+        sg.attach(first_child, "First Grandchild").unwrap();
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.children(root_idx)),
+            vec![(first_child, &"First Child"), (second_child, &"Second Child")]
+        );
+        assert!(sg.children(second_child).next().is_none());
+
+        sg.remove(first_child);
+        assert!(sg.children(first_child).next().is_none());
+    }
+
+    #[test]
+    fn children_rev_yields_in_reverse() {
+        let mut sg = SceneGraph::new("Root");
+        let parent = sg.attach_at_root("Parent");
+        let children = Vec::from_iter(["First", "Second", "Third", "Fourth"].map(|v| sg.attach(parent, v).unwrap()));
+        sg.attach(children[1], "Grandchild").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.children_rev(parent)),
+            vec![
+                (children[3], &"Fourth"),
+                (children[2], &"Third"),
+                (children[1], &"Second"),
+                (children[0], &"First")
+            ]
+        );
+        assert!(sg.children_rev(children[0]).next().is_none());
+
+        let mut forwards = Vec::from_iter(sg.children(NodeIndex::Root));
+        forwards.reverse();
+        assert_eq!(Vec::from_iter(sg.children_rev(NodeIndex::Root)), forwards);
+    }
 }
@@ -0,0 +1,95 @@
+use thunderdome::Index;
+
+use crate::{NodeIndex, SceneGraph};
+
+/// A mutable iterator over only the immediate children of a node in a [SceneGraph].
+/// See [SceneGraph::children_mut] for more information.
+pub struct SceneGraphChildIterMut<'a, T> {
+    sg: &'a mut SceneGraph<T>,
+    current_node: Option<Index>,
+}
+
+impl<'a, T> SceneGraphChildIterMut<'a, T> {
+    pub(crate) fn new(sg: &'a mut SceneGraph<T>, parent_index: NodeIndex) -> Self {
+        let children = match parent_index {
+            NodeIndex::Root => sg.root_children,
+            NodeIndex::Branch(idx) => sg.arena.get(idx).and_then(|v| v.children),
+        };
+
+        SceneGraphChildIterMut {
+            sg,
+            current_node: children.map(|v| v.first),
+        }
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphChildIterMut<'a, T> {
+    type Item = (NodeIndex, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.current_node?;
+        let yield_me = &mut self.sg.arena[idx];
+        self.current_node = yield_me.next_sibling;
+
+        // safety:  this is a lifetime extension, which i know is valid because every sibling is
+        // yielded exactly once, we never touch it again after yielding it, and this iterator
+        // requires `&mut SG` to call `next`.
+        let value: &mut T = unsafe { &mut *(&mut yield_me.value as *mut _) };
+
+        Some((NodeIndex::Branch(idx), value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_nothing_on_empty_iteration() {
+        let mut scene_graph = SceneGraph::new("Root");
+
+        assert!(scene_graph.children_mut(NodeIndex::Root).next().is_none());
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let fg = sg.attach(root_idx, "First Child").unwrap();
+        let first_grandchild = sg.attach(fg, "First Grandchild").unwrap();
+        sg.attach(first_grandchild, "First Greatgrandchild").unwrap();
+        let second_grandchild = sg.attach(fg, "Second Grandchild").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.children_mut(fg).map(|(idx, value)| (idx, *value))),
+            vec![(first_grandchild, "First Grandchild"), (second_grandchild, "Second Grandchild")]
+        );
+    }
+
+    #[test]
+    fn mutates_only_direct_children() {
+        let mut sg = SceneGraph::new(0);
+        let first_child = sg.attach_at_root(0);
+        let grandchild = sg.attach(first_child, 0).unwrap();
+        let second_child = sg.attach_at_root(0);
+
+        for (_, value) in sg.children_mut(NodeIndex::Root) {
+            *value += 1;
+        }
+
+        assert_eq!(sg.get(first_child).unwrap().value, 1);
+        assert_eq!(sg.get(second_child).unwrap().value, 1);
+        assert_eq!(sg.get(grandchild).unwrap().value, 0);
+    }
+
+    #[test]
+    fn leaf_and_invalid_index_yield_nothing() {
+        let mut sg = SceneGraph::new("Root");
+        let leaf = sg.attach_at_root("Leaf");
+        let removed = sg.attach_at_root("Removed");
+        sg.remove(removed);
+
+        assert!(sg.children_mut(leaf).next().is_none());
+        assert!(sg.children_mut(removed).next().is_none());
+    }
+}
@@ -0,0 +1,120 @@
+use thunderdome::{Arena, Index};
+
+use crate::{Node, NodeIndex, SceneGraph};
+
+/// An owning iterator over the SceneGraph, which yields every value, starting with the root, in a
+/// depth first traversal. See [SceneGraph::into_iter] for more information.
+pub struct SceneGraphIntoIter<T> {
+    root: Option<T>,
+    arena: Arena<Node<T>>,
+    stacks: Vec<Index>,
+}
+
+impl<T> SceneGraphIntoIter<T> {
+    pub(crate) fn new(sg: SceneGraph<T>) -> Self {
+        SceneGraphIntoIter {
+            root: Some(sg.root),
+            arena: sg.arena,
+            stacks: Vec::from_iter(sg.root_children.map(|v| v.first)),
+        }
+    }
+}
+
+impl<T> Iterator for SceneGraphIntoIter<T> {
+    type Item = (NodeIndex, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            return Some((NodeIndex::Root, root));
+        }
+
+        // if we're out of stack frames, we die here
+        let idx = self.stacks.pop()?;
+        let node = self.arena.remove(idx).unwrap();
+
+        // if there's a sibling, push it onto the to do list!
+        if let Some(next_sibling) = node.next_sibling {
+            self.stacks.push(next_sibling);
+        }
+
+        if let Some(children) = node.children {
+            self.stacks.push(children.first);
+        }
+
+        Some((NodeIndex::Branch(idx), node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.arena.len() + usize::from(self.root.is_some());
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for SceneGraphIntoIter<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_only_root_on_empty_iteration() {
+        let scene_graph = SceneGraph::new("Root");
+
+        assert_eq!(Vec::from_iter(scene_graph), vec![(NodeIndex::Root, "Root")]);
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let first_child = sg.attach(root_idx, "First Child").unwrap();
+
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        let first_grandchild = sg.attach(second_child, "First Grandchild").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg),
+            vec![
+                (root_idx, "Root"),
+                (first_child, "First Child"),
+                (second_child, "Second Child"),
+                (first_grandchild, "First Grandchild")
+            ]
+        );
+    }
+
+    #[test]
+    fn stagger_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let child = sg.attach(root_idx, "First Child").unwrap();
+        let grandchild = sg.attach(child, "Second Child").unwrap();
+        sg.attach(grandchild, "Third Child").unwrap();
+        sg.attach(root_idx, "Fourth Child").unwrap();
+
+        let mut iter = sg.into_iter();
+        assert_eq!(iter.len(), 5);
+        iter.next();
+        assert_eq!(iter.len(), 4);
+
+        assert_eq!(
+            Vec::from_iter(iter.map(|(_idx, value)| value)),
+            vec!["First Child", "Second Child", "Third Child", "Fourth Child"]
+        );
+    }
+
+    #[test]
+    fn yields_owned_values() {
+        let mut sg = SceneGraph::new(String::from("Root"));
+        let child = sg.attach_at_root(String::from("Child"));
+        sg.attach(child, String::from("Grandchild")).unwrap();
+
+        let mut values = vec![];
+        for (_idx, value) in sg {
+            values.push(value);
+        }
+
+        assert_eq!(values, vec!["Root", "Child", "Grandchild"]);
+    }
+}
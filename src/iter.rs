@@ -109,4 +109,22 @@ mod tests {
             vec!["First Child",]
         );
     }
+
+    #[test]
+    fn values_match_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        assert!(sg.values().next().is_none());
+
+        let root_idx = NodeIndex::Root;
+        sg.attach(root_idx, "First Child").unwrap();
+
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        sg.attach(second_child, "First Grandchild").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.values().cloned()),
+            vec!["First Child", "Second Child", "First Grandchild"]
+        );
+        assert!(sg.values().eq(sg.iter().map(|(_parent, value)| value)));
+    }
 }
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+use thunderdome::Index;
+
+use crate::{NodeIndex, SceneGraph};
+
+/// An iterator over the SceneGraph which visits nodes one generation at a time.
+/// See [iter_bfs] for more information.
+///
+/// [iter_bfs]: SceneGraph::iter_bfs
+pub struct SceneGraphIterBfs<'a, T> {
+    sg: &'a SceneGraph<T>,
+    queue: VecDeque<Index>,
+}
+
+impl<'a, T> SceneGraphIterBfs<'a, T> {
+    pub(crate) fn new(sg: &'a SceneGraph<T>, head_index: NodeIndex) -> Self {
+        let children = match head_index {
+            NodeIndex::Root => sg.root_children,
+            NodeIndex::Branch(idx) => sg.arena[idx].children,
+        };
+
+        SceneGraphIterBfs {
+            sg,
+            queue: VecDeque::from_iter(sg.child_indices(children)),
+        }
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphIterBfs<'a, T> {
+    type Item = (NodeIndex, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // if we're out of nodes, we die here
+        let current_idx = self.queue.pop_front()?;
+        let current_child = &self.sg.arena[current_idx];
+
+        // our children wait until everyone at our depth is done
+        self.queue.extend(self.sg.child_indices(current_child.children));
+
+        Some((NodeIndex::Branch(current_idx), &current_child.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_nothing_on_empty_iteration() {
+        let scene_graph = SceneGraph::new("Root");
+
+        assert!(scene_graph.iter_bfs().next().is_none());
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let a = sg.attach(root_idx, "A").unwrap();
+        let a1 = sg.attach(a, "A1").unwrap();
+        sg.attach(a1, "A1a").unwrap();
+        sg.attach(a, "A2").unwrap();
+        sg.attach(root_idx, "B").unwrap();
+        let c = sg.attach(root_idx, "C").unwrap();
+        sg.attach(c, "C1").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_bfs().map(|(_idx, value)| value).cloned()),
+            vec!["A", "B", "C", "A1", "A2", "C1", "A1a"]
+        );
+    }
+
+    #[test]
+    fn stagger_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let child = sg.attach(root_idx, "First Child").unwrap();
+        let grandchild = sg.attach(child, "Second Child").unwrap();
+        sg.attach(grandchild, "Third Child").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_bfs().map(|(_idx, value)| value).cloned()),
+            vec!["First Child", "Second Child", "Third Child"]
+        );
+    }
+
+    #[test]
+    fn from_branch() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let a = sg.attach(root_idx, "A").unwrap();
+        let a1 = sg.attach(a, "A1").unwrap();
+        sg.attach(a1, "A1a").unwrap();
+        let a2 = sg.attach(a, "A2").unwrap();
+        sg.attach(a2, "A2a").unwrap();
+        let b = sg.attach(root_idx, "B").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_bfs_from(a).unwrap().map(|(_idx, value)| value).cloned()),
+            vec!["A1", "A2", "A1a", "A2a"]
+        );
+        assert_eq!(sg.iter_bfs_from(b).unwrap().count(), 0);
+
+        sg.remove(b);
+        assert!(sg.iter_bfs_from(b).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use thunderdome::Index;
+
+use crate::{NodeIndex, SceneGraph};
+
+/// An iterator over the SceneGraph which yields a [NodeVisit] for every node.
+/// See [iter_detailed] for more information.
+///
+/// [iter_detailed]: SceneGraph::iter_detailed
+pub struct SceneGraphIterDetailed<'a, T> {
+    sg: &'a SceneGraph<T>,
+    stacks: Vec<StackState>,
+    from_root: bool,
+    yielded: usize,
+}
+
+impl<'a, T> SceneGraphIterDetailed<'a, T> {
+    /// Creates an iterator over the descendants of `head_index`, which must be valid. Depths are
+    /// counted from `head_index`, so its children are at depth `1`.
+    pub(crate) fn new(sg: &'a SceneGraph<T>, head_index: NodeIndex) -> Self {
+        let children = match head_index {
+            NodeIndex::Root => sg.root_children,
+            NodeIndex::Branch(idx) => sg.arena[idx].children,
+        };
+
+        let mut stacks = Vec::new();
+        if let Some(children) = children {
+            stacks.push(StackState {
+                parent: head_index,
+                current_child: children.first,
+                depth: 1,
+                sibling_index: 0,
+            });
+        }
+
+        SceneGraphIterDetailed {
+            sg,
+            stacks,
+            from_root: head_index.is_root(),
+            yielded: 0,
+        }
+    }
+
+    /// Rebuilds the stack so that the next node yielded is `target`, exactly as if we had walked
+    /// there. Only valid when iterating from the root.
+    fn jump_to(&mut self, target: Index) {
+        // every node from the target up to the children of the root
+        let mut path = vec![target];
+        while let NodeIndex::Branch(parent) = self.sg.arena[*path.last().unwrap()].parent {
+            path.push(parent);
+        }
+
+        self.stacks.clear();
+        for (depth, &idx) in path.iter().rev().enumerate() {
+            let node = &self.sg.arena[idx];
+            let frame = StackState {
+                parent: node.parent,
+                current_child: idx,
+                depth: depth + 1,
+                sibling_index: self.sg.sibling_position(idx),
+            };
+
+            // for our ancestors, all that's left to do is their later siblings
+            if idx == target {
+                self.stacks.push(frame);
+            } else if let Some(next_sibling) = node.next_sibling {
+                self.stacks.push(StackState {
+                    current_child: next_sibling,
+                    sibling_index: frame.sibling_index + 1,
+                    ..frame
+                });
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphIterDetailed<'a, T> {
+    type Item = NodeVisit<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // if we're out of stack frames, we die here
+        let stack_frame = self.stacks.pop()?;
+        let current_child = &self.sg.arena[stack_frame.current_child];
+        self.yielded += 1;
+
+        // if there's a sibling, push it onto the to do list!
+        if let Some(next_sibling) = current_child.next_sibling {
+            self.stacks.push(StackState {
+                parent: stack_frame.parent,
+                current_child: next_sibling,
+                depth: stack_frame.depth,
+                sibling_index: stack_frame.sibling_index + 1,
+            });
+        }
+
+        if let Some(children) = current_child.children {
+            self.stacks.push(StackState {
+                parent: NodeIndex::Branch(stack_frame.current_child),
+                current_child: children.first,
+                depth: stack_frame.depth + 1,
+                sibling_index: 0,
+            });
+        }
+
+        Some(NodeVisit {
+            index: NodeIndex::Branch(stack_frame.current_child),
+            value: &current_child.value,
+            depth: stack_frame.depth,
+            sibling_index: stack_frame.sibling_index,
+            parent: stack_frame.parent,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.stacks.is_empty() {
+            return (0, Some(0));
+        }
+
+        // we can never see more than the whole arena, and from the root, we see exactly that
+        let remaining = self.sg.len() - self.yielded;
+        if self.from_root {
+            (remaining, Some(remaining))
+        } else {
+            (1, Some(remaining))
+        }
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // if the order is already cached, we can jump right to the node instead of walking there
+        if let Some(order) = self.sg.preorder_cache.get().filter(|_| self.from_root) {
+            let position = self.yielded + n;
+            let Some(&NodeIndex::Branch(target)) = order.get(position) else {
+                self.stacks.clear();
+                self.yielded = order.len();
+                return None;
+            };
+
+            self.jump_to(target);
+            self.yielded = position;
+
+            return self.next();
+        }
+
+        for _ in 0..n {
+            self.next()?;
+        }
+        self.next()
+    }
+}
+
+/// A single node visited by [SceneGraphIterDetailed].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeVisit<'a, T> {
+    /// The index of the node.
+    pub index: NodeIndex,
+    /// The value of the node.
+    pub value: &'a T,
+    /// The number of edges between the root and this node, so the children of the root have a
+    /// depth of `1`.
+    pub depth: usize,
+    /// The position of this node among its siblings, where the first child is `0`.
+    pub sibling_index: usize,
+    /// The index of the node's parent.
+    pub parent: NodeIndex,
+}
+
+// these are written out by hand, since deriving them would require `T: Clone` and `T: Copy`, even
+// though we only hold a reference to `T`
+impl<T> Clone for NodeVisit<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeVisit<'_, T> {}
+
+#[derive(Debug)]
+struct StackState {
+    parent: NodeIndex,
+    current_child: Index,
+    depth: usize,
+    sibling_index: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_nothing_on_empty_iteration() {
+        let scene_graph = SceneGraph::new("Root");
+
+        assert!(scene_graph.iter_detailed().next().is_none());
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        sg.attach(root_idx, "First Child").unwrap();
+
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        sg.attach(second_child, "First Grandchild").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_detailed().map(|visit| *visit.value)),
+            vec!["First Child", "Second Child", "First Grandchild"]
+        );
+    }
+
+    #[test]
+    fn nth_matches_skip() {
+        let sg = crate::scene_graph!("Root" => {
+            "A" => { "A1" => { "A1a", "A1b" }, "A2" },
+            "B",
+            "C" => { "C1" => { "C1a" }, "C2" },
+        });
+
+        let check = |sg: &SceneGraph<&'static str>| {
+            for k in 0..sg.len() + 2 {
+                // walk there by hand, since `skip` just calls `nth`
+                let mut walked = sg.iter_detailed();
+                for _ in 0..k {
+                    walked.next();
+                }
+
+                assert_eq!(sg.iter_detailed().nth(k), walked.next(), "{}", k);
+            }
+
+            // nth works from the middle of an iteration too
+            let mut iter = sg.iter_detailed();
+            iter.next();
+            assert_eq!(iter.nth(3).map(|visit| *visit.value), Some("A2"));
+            assert_eq!(iter.next().map(|visit| *visit.value), Some("B"));
+            assert_eq!(
+                iter.nth(2).map(|visit| (*visit.value, visit.depth, visit.sibling_index)),
+                Some(("C1a", 3, 0))
+            );
+            assert_eq!(Vec::from_iter(iter.map(|visit| *visit.value)), vec!["C2"]);
+        };
+
+        check(&sg);
+
+        sg.preorder_indices();
+        assert!(sg.preorder_cache.get().is_some());
+        check(&sg);
+    }
+
+    #[test]
+    fn visits_copy_without_copy_values() {
+        let mut sg = SceneGraph::new(String::from("Root"));
+        sg.attach_at_root(String::from("Child"));
+
+        let visit = sg.iter_detailed().next().unwrap();
+        let copied = visit;
+        assert_eq!(visit.value, "Child");
+        assert_eq!(copied, visit.clone());
+    }
+
+    #[test]
+    fn size_hint_is_consistent() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let first_child = sg.attach(root_idx, "First Child").unwrap();
+        sg.attach(first_child, "First Grandchild").unwrap();
+        sg.attach(root_idx, "Second Child").unwrap();
+
+        let mut iter = sg.iter_detailed();
+        for remaining in (0..=sg.len()).rev() {
+            assert_eq!(iter.size_hint(), (remaining, Some(remaining)));
+            iter.next();
+        }
+
+        let mut iter = SceneGraphIterDetailed::new(&sg, first_child);
+        let (lower, upper) = iter.size_hint();
+        assert!(lower <= 1 && upper.unwrap() >= 1);
+        iter.next();
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+    }
+
+    #[test]
+    fn reports_positions() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let first_child = sg.attach(root_idx, "First Child").unwrap();
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        let first_grandchild = sg.attach(second_child, "First Grandchild").unwrap();
+        let second_grandchild = sg.attach(second_child, "Second Grandchild").unwrap();
+        let greatgrandchild = sg.attach(second_grandchild, "First Greatgrandchild").unwrap();
+        let third_child = sg.attach(root_idx, "Third Child").unwrap();
+
+        let visits = Vec::from_iter(sg.iter_detailed().map(|v| (v.index, v.depth, v.sibling_index, v.parent)));
+
+        assert_eq!(
+            visits,
+            vec![
+                (first_child, 1, 0, root_idx),
+                (second_child, 1, 1, root_idx),
+                (first_grandchild, 2, 0, second_child),
+                (second_grandchild, 2, 1, second_child),
+                (greatgrandchild, 3, 0, second_grandchild),
+                (third_child, 1, 2, root_idx),
+            ]
+        );
+    }
+}
@@ -5,19 +5,24 @@ use crate::{Node, NodeIndex, SceneGraph};
 /// A mutable iterator over the children of a node in a [SceneGraph],
 /// that skips branches/subtrees where the predicate is not fulfilled.
 /// See [SceneGraph::iter_mut_predicate] for more information.
-pub struct SceneGraphIterMutPredicate<'a, T> {
+pub struct SceneGraphIterMutPredicate<'a, T, F> {
     sg: &'a mut SceneGraph<T>,
-    predicate: fn(&T) -> bool,
+    predicate: F,
     stacks: Vec<StackState>,
 }
 
-impl<'a, T> SceneGraphIterMutPredicate<'a, T> {
-    pub(crate) fn new(sg: &'a mut SceneGraph<T>, root_node_idx: NodeIndex, predicate: fn(&T) -> bool) -> Self {
+impl<'a, T, F: FnMut(&T) -> bool> SceneGraphIterMutPredicate<'a, T, F> {
+    pub(crate) fn new(sg: &'a mut SceneGraph<T>, root_node_idx: NodeIndex, mut predicate: F) -> Self {
         let mut stacks = Vec::new();
 
+        // the root is checked as we go, but any other starting node has to be checked up front
         let first_child = match root_node_idx {
             NodeIndex::Root => sg.root_children.map(|v| v.first),
-            NodeIndex::Branch(idx) => sg.arena.get(idx).and_then(|v| v.children.map(|v| v.first)),
+            NodeIndex::Branch(idx) => sg
+                .arena
+                .get(idx)
+                .filter(|v| predicate(&v.value))
+                .and_then(|v| v.children.map(|v| v.first)),
         };
 
         if let Some(first_child) = first_child {
@@ -31,7 +36,7 @@ impl<'a, T> SceneGraphIterMutPredicate<'a, T> {
     }
 }
 
-impl<'a, T> Iterator for SceneGraphIterMutPredicate<'a, T> {
+impl<'a, T, F: FnMut(&T) -> bool> Iterator for SceneGraphIterMutPredicate<'a, T, F> {
     type Item = (&'a mut T, &'a mut T);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -41,7 +46,7 @@ impl<'a, T> Iterator for SceneGraphIterMutPredicate<'a, T> {
             let (parent, current_child) = match stack_frame.parent {
                 NodeIndex::Root => {
                     let parent = &mut self.sg.root;
-                    if !(self.predicate)(&parent) {
+                    if !(self.predicate)(parent) {
                         // The root does not fulfill the predicate, the whole graph will be skipped
                         continue;
                     }
@@ -81,7 +86,42 @@ impl<'a, T> Iterator for SceneGraphIterMutPredicate<'a, T> {
 
             return Some((parent, &mut current_child.value));
         }
-        return None;
+        None
+    }
+}
+
+/// A mutable iterator like the one from [SceneGraph::iter_mut_predicate], which ends for good
+/// once a yielded node fulfills `stop`. See [SceneGraph::iter_mut_predicate_with_stop] for more
+/// information.
+pub struct SceneGraphIterMutPredicateWithStop<'a, T, F, S> {
+    iter: SceneGraphIterMutPredicate<'a, T, F>,
+    stop: S,
+    stopped: bool,
+}
+
+impl<'a, T, F: FnMut(&T) -> bool, S: FnMut(&T) -> bool> SceneGraphIterMutPredicateWithStop<'a, T, F, S> {
+    pub(crate) fn new(iter: SceneGraphIterMutPredicate<'a, T, F>, stop: S) -> Self {
+        Self {
+            iter,
+            stop,
+            stopped: false,
+        }
+    }
+}
+
+impl<'a, T, F: FnMut(&T) -> bool, S: FnMut(&T) -> bool> Iterator for SceneGraphIterMutPredicateWithStop<'a, T, F, S> {
+    type Item = (&'a mut T, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // checked first, so that nothing past the stop node is ever walked
+        if self.stopped {
+            return None;
+        }
+
+        let (parent, value) = self.iter.next()?;
+        self.stopped = (self.stop)(value);
+
+        Some((parent, value))
     }
 }
 
@@ -108,7 +148,7 @@ mod tests {
     fn scene_graph_returns_nothing_on_empty_iteration() {
         let mut scene_graph = SceneGraph::new("Root");
 
-        assert!(scene_graph.iter_mut_predicate(|node| {true}).next().is_none());
+        assert!(scene_graph.iter_mut_predicate(|_node| true).next().is_none());
     }
 
     #[test]
@@ -122,7 +162,7 @@ mod tests {
 
         assert_eq!(
             Vec::from_iter(sg
-                .iter_mut_predicate(|node| {true})
+                .iter_mut_predicate(|_node| true)
                 .map(|(_parent, value)| &*value)
                 .copied()),
             vec!["First Child", "Second Child", "First Grandchild"]
@@ -138,7 +178,7 @@ mod tests {
 
         assert_eq!(
             Vec::from_iter(sg
-                .iter_mut_predicate(|node| {true})
+                .iter_mut_predicate(|_node| true)
                 .map(|(_parent, value)| &*value)
                 .copied()),
             vec!["First Child", "Second Child"]
@@ -153,7 +193,7 @@ mod tests {
 
         assert_eq!(
             Vec::from_iter(sg
-                .iter_mut_predicate(|node| {true})
+                .iter_mut_predicate(|_node| true)
                 .map(|(_parent, value)| &*value).copied()),
             vec!["First Child",]
         );
@@ -163,7 +203,7 @@ mod tests {
     fn visits_none_when_root_does_not_match() {
         let mut sg = SceneGraph::new(ConditionalNode::new("Root",false));
         let root_idx = NodeIndex::Root;
-        let c1 = sg.attach(root_idx, ConditionalNode::new("Child 1", true)).unwrap();
+        sg.attach(root_idx, ConditionalNode::new("Child 1", true)).unwrap();
 
         assert_eq!(0, sg.iter_mut_predicate(|node| {node.condition}).count());
     }
@@ -174,10 +214,10 @@ mod tests {
         let root_idx = NodeIndex::Root;
         let c1 = sg.attach(root_idx, ConditionalNode::new("Child 1", true)).unwrap();
         let c2 = sg.attach(root_idx, ConditionalNode::new("Child 2", false)).unwrap();
-        let c3 = sg.attach(root_idx, ConditionalNode::new("Child 3", true)).unwrap();
-        let c1c = sg.attach(c1, ConditionalNode::new("Child of child 1", true)).unwrap();
+        sg.attach(root_idx, ConditionalNode::new("Child 3", true)).unwrap();
+        sg.attach(c1, ConditionalNode::new("Child of child 1", true)).unwrap();
         // Should be skipped due to c2 being set to false
-        let c2c = sg.attach(c2, ConditionalNode::new("Child of child 2", true)).unwrap();
+        sg.attach(c2, ConditionalNode::new("Child of child 2", true)).unwrap();
 
         assert_eq!(
             Vec::from_iter(sg
@@ -187,6 +227,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn predicate_can_capture_state() {
+        let mut sg = SceneGraph::new(0);
+        let root_idx = NodeIndex::Root;
+        let c1 = sg.attach(root_idx, 1).unwrap();
+        sg.attach(c1, 2).unwrap();
+        let c3 = sg.attach(root_idx, 30).unwrap();
+        sg.attach(c3, 4).unwrap();
+        sg.attach(root_idx, 5).unwrap();
+
+        let limit = 10;
+        let mut checked = 0;
+
+        let visited = Vec::from_iter(
+            sg.iter_mut_predicate(|node| {
+                checked += 1;
+                *node < limit
+            })
+            .map(|(_parent, value)| *value),
+        );
+
+        assert_eq!(visited, vec![1, 2, 5]);
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn stops_after_the_stop_node() {
+        let mut sg = SceneGraph::new(ConditionalNode::new("Root", true));
+        let root_idx = NodeIndex::Root;
+        let c1 = sg.attach(root_idx, ConditionalNode::new("Child 1", true)).unwrap();
+        sg.attach(c1, ConditionalNode::new("Target", true)).unwrap();
+        sg.attach(c1, ConditionalNode::new("Sibling of target", true)).unwrap();
+        sg.attach(root_idx, ConditionalNode::new("Child 2", true)).unwrap();
+
+        let mut iter = sg.iter_mut_predicate_with_stop(|node| node.condition, |node| node.name == "Target");
+        let mut visited = vec![];
+        for (_parent, value) in iter.by_ref() {
+            value.condition = false;
+            visited.push(value.name);
+        }
+        assert_eq!(visited, vec!["Child 1", "Target"]);
+
+        // even though more nodes match, we're done for good
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+        drop(iter);
+
+        assert_eq!(
+            Vec::from_iter(sg.iter().filter(|(_, node)| node.condition).map(|(_, node)| node.name)),
+            vec!["Sibling of target", "Child 2"]
+        );
+    }
+
+    #[test]
+    fn walks_nothing_after_the_stop_node() {
+        let mut sg = SceneGraph::new(ConditionalNode::new("Root", true));
+        let root_idx = NodeIndex::Root;
+        sg.attach(root_idx, ConditionalNode::new("Target", true)).unwrap();
+        sg.attach(root_idx, ConditionalNode::new("Child 2", true)).unwrap();
+        sg.attach(root_idx, ConditionalNode::new("Child 3", true)).unwrap();
+
+        let mut checked = vec![];
+        let mut iter = sg.iter_mut_predicate_with_stop(
+            |node| {
+                checked.push(node.name);
+                node.condition
+            },
+            |node| node.name == "Target",
+        );
+        assert_eq!(iter.next().map(|(_parent, value)| value.name), Some("Target"));
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+        drop(iter);
+
+        // the target is checked before it's yielded, but neither of its siblings ever is
+        assert_eq!(checked, vec!["Root", "Target"]);
+    }
+
+    #[test]
+    fn from_node_stays_in_its_subtree() {
+        let mut sg = SceneGraph::new(ConditionalNode::new("Root", true));
+        let root_idx = NodeIndex::Root;
+        let c1 = sg.attach(root_idx, ConditionalNode::new("Child 1", true)).unwrap();
+        let g1 = sg.attach(c1, ConditionalNode::new("Grandchild 1", true)).unwrap();
+        sg.attach(g1, ConditionalNode::new("Greatgrandchild 1", true)).unwrap();
+        let g2 = sg.attach(c1, ConditionalNode::new("Grandchild 2", false)).unwrap();
+        // Should be skipped due to g2 being set to false
+        sg.attach(g2, ConditionalNode::new("Greatgrandchild 2", true)).unwrap();
+        sg.attach(root_idx, ConditionalNode::new("Child 2", true)).unwrap();
+
+        let mut checked = vec![];
+        let visited = Vec::from_iter(
+            sg.iter_mut_predicate_from(c1, |node| {
+                checked.push(node.name);
+                node.condition
+            })
+            .unwrap()
+            .map(|(parent, value)| (parent.name, value.name)),
+        );
+
+        assert_eq!(
+            visited,
+            vec![("Child 1", "Grandchild 1"), ("Grandchild 1", "Greatgrandchild 1")]
+        );
+        assert!(!checked.contains(&"Root"));
+        assert!(!checked.contains(&"Child 2"));
+        assert!(!checked.contains(&"Greatgrandchild 2"));
+
+        // the starting node gates everything beneath it
+        assert_eq!(sg.iter_mut_predicate_from(g2, |node| node.condition).unwrap().count(), 0);
+
+        sg.remove(g1);
+        assert!(sg.iter_mut_predicate_from(g1, |node| node.condition).is_err());
+    }
+
     #[derive(PartialEq, Clone)]
     struct ConditionalNode {
         pub name: &'static str,
@@ -0,0 +1,136 @@
+use thunderdome::Index;
+
+use crate::{NodeIndex, SceneGraph};
+
+/// An iterator over the SceneGraph which visits children before their parents.
+/// See [SceneGraph::iter_post_order] for more information.
+pub struct SceneGraphIterPostOrder<'a, T> {
+    sg: &'a SceneGraph<T>,
+    stacks: Vec<StackState>,
+}
+
+impl<'a, T> SceneGraphIterPostOrder<'a, T> {
+    pub(crate) fn new(sg: &'a SceneGraph<T>, root_node_idx: NodeIndex) -> Self {
+        let mut stacks = Vec::new();
+
+        let first_child = match root_node_idx {
+            NodeIndex::Root => sg.root_children.map(|v| v.first),
+            NodeIndex::Branch(idx) => sg.arena.get(idx).and_then(|v| v.children.map(|v| v.first)),
+        };
+
+        if let Some(first_child) = first_child {
+            stacks.push(StackState::new(first_child));
+        };
+        SceneGraphIterPostOrder { sg, stacks }
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphIterPostOrder<'a, T> {
+    type Item = (NodeIndex, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // if we're out of stack frames, we die here
+            let stack_frame = self.stacks.last_mut()?;
+
+            // the first time we see a node, we go handle its children instead
+            if !stack_frame.children_visited {
+                stack_frame.children_visited = true;
+
+                if let Some(first_child) = self.sg.arena[stack_frame.current_child].children.map(|v| v.first) {
+                    self.stacks.push(StackState::new(first_child));
+                    continue;
+                }
+            }
+
+            let stack_frame = self.stacks.pop().unwrap();
+            let current_child = &self.sg.arena[stack_frame.current_child];
+
+            // if there's a sibling, it's next up
+            if let Some(next_sibling) = current_child.next_sibling {
+                self.stacks.push(StackState::new(next_sibling));
+            }
+
+            return Some((NodeIndex::Branch(stack_frame.current_child), &current_child.value));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct StackState {
+    current_child: Index,
+    children_visited: bool,
+}
+
+impl StackState {
+    fn new(current_child: Index) -> Self {
+        Self {
+            current_child,
+            children_visited: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_nothing_on_empty_iteration() {
+        let scene_graph = SceneGraph::new("Root");
+
+        assert!(scene_graph.iter_post_order().next().is_none());
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        sg.attach(root_idx, "First Child").unwrap();
+
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        sg.attach(second_child, "First Grandchild").unwrap();
+        sg.attach(second_child, "Second Grandchild").unwrap();
+        sg.attach(second_child, "Third Grandchild").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_post_order().map(|(_idx, value)| value).copied()),
+            vec![
+                "First Child",
+                "First Grandchild",
+                "Second Grandchild",
+                "Third Grandchild",
+                "Second Child"
+            ]
+        );
+    }
+
+    #[test]
+    fn stagger_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let a = sg.attach(root_idx, "A").unwrap();
+        let b = sg.attach(a, "B").unwrap();
+        sg.attach(b, "C").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_post_order().map(|(_idx, value)| value).copied()),
+            vec!["C", "B", "A"]
+        );
+    }
+
+    #[test]
+    fn matches_mutable_order() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let a = sg.attach(root_idx, "A").unwrap();
+        let a1 = sg.attach(a, "A1").unwrap();
+        sg.attach(a1, "A1a").unwrap();
+        sg.attach(a, "A2").unwrap();
+        sg.attach(root_idx, "B").unwrap();
+
+        let order = Vec::from_iter(sg.iter_post_order().map(|(idx, _value)| idx));
+
+        assert_eq!(order, Vec::from_iter(sg.iter_post_order_mut().map(|(idx, _value)| idx)));
+    }
+}
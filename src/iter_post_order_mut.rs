@@ -0,0 +1,147 @@
+use thunderdome::Index;
+
+use crate::{NodeIndex, SceneGraph};
+
+/// A mutable iterator over the SceneGraph which visits children before their parents.
+/// See [SceneGraph::iter_post_order_mut] for more information.
+pub struct SceneGraphIterPostOrderMut<'a, T> {
+    sg: &'a mut SceneGraph<T>,
+    stacks: Vec<StackState>,
+}
+
+impl<'a, T> SceneGraphIterPostOrderMut<'a, T> {
+    pub(crate) fn new(sg: &'a mut SceneGraph<T>, root_node_idx: NodeIndex) -> Self {
+        let mut stacks = Vec::new();
+
+        let first_child = match root_node_idx {
+            NodeIndex::Root => sg.root_children.map(|v| v.first),
+            NodeIndex::Branch(idx) => sg.arena.get(idx).and_then(|v| v.children.map(|v| v.first)),
+        };
+
+        if let Some(first_child) = first_child {
+            stacks.push(StackState::new(first_child));
+        };
+        SceneGraphIterPostOrderMut { sg, stacks }
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphIterPostOrderMut<'a, T> {
+    type Item = (NodeIndex, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // if we're out of stack frames, we die here
+            let stack_frame = self.stacks.last_mut()?;
+
+            // the first time we see a node, we go handle its children instead
+            if !stack_frame.children_visited {
+                stack_frame.children_visited = true;
+
+                if let Some(first_child) = self.sg.arena[stack_frame.current_child].children.map(|v| v.first) {
+                    self.stacks.push(StackState::new(first_child));
+                    continue;
+                }
+            }
+
+            let stack_frame = self.stacks.pop().unwrap();
+            let current_child = self.sg.arena.get_mut(stack_frame.current_child).unwrap();
+
+            // if there's a sibling, it's next up
+            if let Some(next_sibling) = current_child.next_sibling {
+                self.stacks.push(StackState::new(next_sibling));
+            }
+
+            // safety:  this is a lifetime extension, which i know is valid because every node is
+            // yielded exactly once, we never touch a node again after yielding it, and this
+            // iterator requires `&mut SG` to call `next`.
+            let value: &mut T = unsafe { &mut *(&mut current_child.value as *mut _) };
+
+            return Some((NodeIndex::Branch(stack_frame.current_child), value));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct StackState {
+    current_child: Index,
+    children_visited: bool,
+}
+
+impl StackState {
+    fn new(current_child: Index) -> Self {
+        Self {
+            current_child,
+            children_visited: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_nothing_on_empty_iteration() {
+        let mut scene_graph = SceneGraph::new("Root");
+
+        assert!(scene_graph.iter_post_order_mut().next().is_none());
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        sg.attach(root_idx, "First Child").unwrap();
+
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        sg.attach(second_child, "First Grandchild").unwrap();
+        sg.attach(second_child, "Second Grandchild").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_post_order_mut().map(|(_idx, value)| &*value).copied()),
+            vec!["First Child", "First Grandchild", "Second Grandchild", "Second Child"]
+        );
+    }
+
+    #[test]
+    fn stagger_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let child = sg.attach(root_idx, "First Child").unwrap();
+        let grandchild = sg.attach(child, "Second Child").unwrap();
+        sg.attach(grandchild, "Third Child").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_post_order_mut().map(|(_idx, value)| &*value).copied()),
+            vec!["Third Child", "Second Child", "First Child"]
+        );
+    }
+
+    #[test]
+    fn children_are_mutated_first() {
+        // each node's value becomes the size of its subtree
+        let mut sg = SceneGraph::new(0);
+        let first_child = sg.attach_at_root(0);
+        let grandchild = sg.attach(first_child, 0).unwrap();
+        sg.attach(grandchild, 0).unwrap();
+        sg.attach(grandchild, 0).unwrap();
+        sg.attach(first_child, 0).unwrap();
+        let second_child = sg.attach_at_root(0);
+
+        let parents: HashMap<_, _> = sg.iter_detailed().map(|visit| (visit.index, visit.parent)).collect();
+        let mut child_sizes: HashMap<NodeIndex, usize> = HashMap::new();
+
+        for (idx, value) in sg.iter_post_order_mut() {
+            // if our children weren't already done, they'd be missing from this
+            *value = 1 + child_sizes.get(&idx).copied().unwrap_or(0);
+            *child_sizes.entry(parents[&idx]).or_insert(0) += *value;
+        }
+
+        assert_eq!(sg.get(first_child).unwrap().value, 5);
+        assert_eq!(sg.get(grandchild).unwrap().value, 3);
+        assert_eq!(sg.get(second_child).unwrap().value, 1);
+        assert_eq!(child_sizes[&NodeIndex::Root], 6);
+    }
+}
@@ -0,0 +1,87 @@
+use thunderdome::Index;
+
+use crate::{NodeIndex, SceneGraph};
+
+/// An iterator over the SceneGraph which visits siblings in reverse order.
+/// See [iter_rev] for more information.
+///
+/// [iter_rev]: SceneGraph::iter_rev
+pub struct SceneGraphIterRev<'a, T> {
+    sg: &'a SceneGraph<T>,
+    stacks: Vec<Index>,
+}
+
+impl<'a, T> SceneGraphIterRev<'a, T> {
+    pub(crate) fn new(sg: &'a SceneGraph<T>) -> Self {
+        let mut stacks = Vec::new();
+        if let Some(last_child) = sg.root_children.map(|v| v.last) {
+            stacks.push(last_child);
+        };
+        SceneGraphIterRev { sg, stacks }
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphIterRev<'a, T> {
+    type Item = (NodeIndex, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // if we're out of stack frames, we die here
+        let current_idx = self.stacks.pop()?;
+        let current_child = &self.sg.arena[current_idx];
+
+        // our older sibling goes on the to do list, after our own children
+        if let Some(last_sibling) = current_child.last_sibling {
+            self.stacks.push(last_sibling);
+        }
+
+        if let Some(last_child) = current_child.children.map(|v| v.last) {
+            self.stacks.push(last_child);
+        }
+
+        Some((NodeIndex::Branch(current_idx), &current_child.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_nothing_on_empty_iteration() {
+        let scene_graph = SceneGraph::new("Root");
+
+        assert!(scene_graph.iter_rev().next().is_none());
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let a = sg.attach(root_idx, "A").unwrap();
+        sg.attach(a, "A1").unwrap();
+        sg.attach(a, "A2").unwrap();
+        sg.attach(root_idx, "B").unwrap();
+        let c = sg.attach(root_idx, "C").unwrap();
+        let c1 = sg.attach(c, "C1").unwrap();
+        sg.attach(c1, "C1a").unwrap();
+        sg.attach(c, "C2").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_rev().map(|(_idx, value)| value).cloned()),
+            vec!["C", "C2", "C1", "C1a", "B", "A", "A2", "A1"]
+        );
+    }
+
+    #[test]
+    fn stagger_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let child = sg.attach(root_idx, "First Child").unwrap();
+        sg.attach(child, "Second Child").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_rev().map(|(_idx, value)| value).cloned()),
+            vec!["First Child", "Second Child"]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use thunderdome::Index;
+
+use crate::{NodeIndex, SceneGraph};
+
+/// An iterator over the SceneGraph which doesn't descend into certain nodes.
+/// See [iter_skipping] for more information.
+///
+/// [iter_skipping]: SceneGraph::iter_skipping
+pub struct SceneGraphIterSkipping<'a, T> {
+    sg: &'a SceneGraph<T>,
+    skip: &'a HashSet<NodeIndex>,
+    stacks: Vec<Index>,
+}
+
+impl<'a, T> SceneGraphIterSkipping<'a, T> {
+    pub(crate) fn new(sg: &'a SceneGraph<T>, skip: &'a HashSet<NodeIndex>) -> Self {
+        let mut stacks = Vec::new();
+        if let Some(first_child) = sg.root_children.map(|v| v.first) {
+            stacks.push(first_child);
+        };
+        SceneGraphIterSkipping { sg, skip, stacks }
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphIterSkipping<'a, T> {
+    type Item = (NodeIndex, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // if we're out of stack frames, we die here
+        let current_idx = self.stacks.pop()?;
+        let current_child = &self.sg.arena[current_idx];
+        let node_index = NodeIndex::Branch(current_idx);
+
+        // if there's a sibling, push it onto the to do list!
+        if let Some(next_sibling) = current_child.next_sibling {
+            self.stacks.push(next_sibling);
+        }
+
+        // skipped nodes are still yielded, we just don't go beneath them
+        if !self.skip.contains(&node_index) {
+            if let Some(first_child) = current_child.children.map(|v| v.first) {
+                self.stacks.push(first_child);
+            }
+        }
+
+        Some((node_index, &current_child.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_graph_returns_nothing_on_empty_iteration() {
+        let scene_graph = SceneGraph::new("Root");
+
+        assert!(scene_graph.iter_skipping(&HashSet::new()).next().is_none());
+    }
+
+    #[test]
+    fn normal_iteration() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        sg.attach(root_idx, "First Child").unwrap();
+
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        sg.attach(second_child, "First Grandchild").unwrap();
+        sg.attach(root_idx, "Third Child").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_skipping(&HashSet::new()).map(|(_idx, value)| value).cloned()),
+            vec!["First Child", "Second Child", "First Grandchild", "Third Child"]
+        );
+    }
+
+    #[test]
+    fn skipped_branches_are_not_descended() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        let first_child = sg.attach(root_idx, "First Child").unwrap();
+        sg.attach(first_child, "First Grandchild").unwrap();
+
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        let second_grandchild = sg.attach(second_child, "Second Grandchild").unwrap();
+        sg.attach(second_grandchild, "Greatgrandchild").unwrap();
+        sg.attach(second_child, "Third Grandchild").unwrap();
+        sg.attach(root_idx, "Third Child").unwrap();
+
+        let skip = HashSet::from([second_child]);
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_skipping(&skip).map(|(_idx, value)| value).cloned()),
+            vec!["First Child", "First Grandchild", "Second Child", "Third Child"]
+        );
+    }
+}
@@ -3,19 +3,48 @@
 #![deny(missing_docs)]
 #![deny(rustdoc::broken_intra_doc_links)]
 
-use std::{cmp::Eq, collections::HashMap};
+use std::{
+    cmp::Eq,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::OnceLock,
+};
 use thunderdome::{Arena, Index};
 
+mod ancestors;
 mod child_iter;
+mod child_iter_mut;
 mod detatch_iter;
+mod into_iter;
 mod iter;
+mod iter_bfs;
+mod iter_detailed;
 mod iter_mut;
 mod iter_mut_predicate;
+mod iter_predicate;
+mod iter_post_order;
+mod iter_post_order_mut;
+mod iter_rev;
+mod iter_skipping;
+mod macros;
+#[cfg(feature = "serde")]
+mod serde_impl;
 
+pub use ancestors::SceneGraphAncestors;
 pub use child_iter::SceneGraphChildIter;
+pub use child_iter_mut::SceneGraphChildIterMut;
 pub use detatch_iter::{DetachedNode, SceneGraphDetachIter};
+pub use into_iter::SceneGraphIntoIter;
 pub use iter::SceneGraphIter;
+pub use iter_bfs::SceneGraphIterBfs;
+pub use iter_detailed::{NodeVisit, SceneGraphIterDetailed};
 pub use iter_mut::SceneGraphIterMut;
+pub use iter_mut_predicate::SceneGraphIterMutPredicateWithStop;
+pub use iter_post_order::SceneGraphIterPostOrder;
+pub use iter_post_order_mut::SceneGraphIterPostOrderMut;
+pub use iter_predicate::SceneGraphIterPredicate;
+pub use iter_rev::SceneGraphIterRev;
+pub use iter_skipping::SceneGraphIterSkipping;
 use crate::iter_mut_predicate::SceneGraphIterMutPredicate;
 
 /// The core structure of `scene-graph`. This forms a rose tree, similar to a geneological tree.
@@ -36,6 +65,8 @@ pub struct SceneGraph<T> {
     pub root: T,
     arena: Arena<Node<T>>,
     root_children: Option<Children>,
+    preorder_cache: OnceLock<Vec<NodeIndex>>,
+    max_nodes: Option<usize>,
 }
 
 impl<T> SceneGraph<T> {
@@ -45,29 +76,154 @@ impl<T> SceneGraph<T> {
             arena: Arena::new(),
             root,
             root_children: None,
+            preorder_cache: OnceLock::new(),
+            max_nodes: None,
         }
     }
 
+    /// Creates a new `SceneGraph` with a `Default` root.
+    ///
+    /// None of the traversals yield the root, so this is useful when the root is purely
+    /// structural.
+    pub fn new_default() -> Self
+    where
+        T: Default,
+    {
+        Self::new(T::default())
+    }
+
     /// Creates a new `SceneGraph` where the backing Arena will have the given initial capacity.
     pub fn with_capacity(root: T, capacity: usize) -> Self {
       Self {
         arena: Arena::with_capacity(capacity),
         root,
         root_children: None,
+        preorder_cache: OnceLock::new(),
+        max_nodes: None,
       }
     }
 
+    /// Builds a `SceneGraph` from a flat list of `(parent, value)` entries, where `parent` is the
+    /// position of the entry's parent within `items`. Exactly one entry must have a parent of
+    /// `None`, and that entry becomes the root.
+    ///
+    /// Entries may be given in any order. Siblings keep the order they had in `items`. On success,
+    /// this also returns the `NodeIndex` of each entry, in the same order as `items`.
+    pub fn from_parent_array(
+        items: Vec<(Option<usize>, T)>,
+    ) -> Result<(SceneGraph<T>, Vec<NodeIndex>), ParentArrayError> {
+        let mut root_entry = None;
+        let mut children: Vec<Vec<usize>> = items.iter().map(|_| Vec::new()).collect();
+
+        for (entry, (parent, _)) in items.iter().enumerate() {
+            match *parent {
+                None => {
+                    if root_entry.replace(entry).is_some() {
+                        return Err(ParentArrayError::MultipleRoots);
+                    }
+                }
+                Some(parent) => {
+                    let parent_children = children.get_mut(parent).ok_or(ParentArrayError::DanglingParent(entry))?;
+                    parent_children.push(entry);
+                }
+            }
+        }
+
+        let root_entry = root_entry.ok_or(ParentArrayError::NoRoot)?;
+
+        let mut values: Vec<Option<T>> = items.into_iter().map(|(_, value)| Some(value)).collect();
+        let root = values[root_entry].take().unwrap();
+        let mut sg = SceneGraph::with_capacity(root, values.len() - 1);
+
+        let mut indices = vec![None; values.len()];
+        indices[root_entry] = Some(NodeIndex::Root);
+
+        let mut stacks = vec![root_entry];
+        while let Some(parent) = stacks.pop() {
+            let parent_idx = indices[parent].unwrap();
+
+            for &child in children[parent].iter() {
+                let new_idx = sg.attach(parent_idx, values[child].take().unwrap()).unwrap();
+                indices[child] = Some(new_idx);
+
+                stacks.push(child);
+            }
+        }
+
+        // anything we couldn't reach from the root must be stuck in a cycle
+        let indices = indices
+            .into_iter()
+            .enumerate()
+            .map(|(entry, idx)| idx.ok_or(ParentArrayError::Disconnected(entry)))
+            .collect::<Result<_, _>>()?;
+
+        Ok((sg, indices))
+    }
+
+    /// Breaks the SceneGraph into its raw parts: the root value, the root's children, and the
+    /// backing Arena. These can be put back together with [from_parts].
+    ///
+    /// [from_parts]: Self::from_parts
+    pub fn into_parts(self) -> (T, Option<Children>, Arena<Node<T>>) {
+        (self.root, self.root_children, self.arena)
+    }
+
+    /// Builds a SceneGraph from its raw parts, as returned by [into_parts].
+    ///
+    /// The parts are checked before being accepted: every link between nodes must resolve, every
+    /// node must agree with its neighbors about who its parent and siblings are, there must be no
+    /// cycles, and every node in the arena must be reachable from the root.
+    ///
+    /// The parts can also be built by hand, using [Node::new] and its link setters:
+    ///
+    /// ```
+    /// use scene_graph::{Children, Node, NodeIndex, SceneGraph};
+    /// use thunderdome::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// let first = arena.insert(Node::new("First", NodeIndex::Root));
+    /// let second = arena.insert(Node::new("Second", NodeIndex::Root));
+    /// arena[first].set_next_sibling(Some(second));
+    /// arena[second].set_last_sibling(Some(first));
+    ///
+    /// let root_children = Children { first, last: second, count: 2 };
+    /// let sg = SceneGraph::from_parts("Root", Some(root_children), arena).unwrap();
+    ///
+    /// assert_eq!(Vec::from_iter(sg.values().copied()), ["First", "Second"]);
+    /// ```
+    ///
+    /// [into_parts]: Self::into_parts
+    pub fn from_parts(
+        root: T,
+        root_children: Option<Children>,
+        arena: Arena<Node<T>>,
+    ) -> Result<Self, InvalidParts> {
+        let sg = Self {
+            root,
+            arena,
+            root_children,
+            preorder_cache: OnceLock::new(),
+            max_nodes: None,
+        };
+        sg.check_links()?;
+
+        Ok(sg)
+    }
+
     /// Clears all nodes from `self`, leaving the `Root` in place. If you want to edit the root too,
-    /// just make a new SceneGraph.
+    /// just make a new SceneGraph. Every `NodeIndex::Branch` handed out before this is no longer
+    /// valid afterwards.
     ///
     /// Note: this method maintains the underlying container's size, so future attaches could have
     /// some performance gains.
     pub fn clear(&mut self) {
         self.arena.clear();
         self.root_children = None;
+        self.preorder_cache.take();
     }
 
-    /// Returns the number of NON-ROOT nodes in the graph.
+    /// Returns the number of NON-ROOT nodes in the graph. This is O1, since it doesn't walk the
+    /// graph.
     pub fn len(&self) -> usize {
         self.arena.len()
     }
@@ -77,29 +233,120 @@ impl<T> SceneGraph<T> {
         self.root_children.is_none()
     }
 
+    /// Returns the number of nodes in the graph, NOT counting the root. This is the same as
+    /// [len], spelled out.
+    ///
+    /// [len]: Self::len
+    pub fn branch_count(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns the number of nodes in the graph, counting the root, which is always one more than
+    /// [branch_count].
+    ///
+    /// [branch_count]: Self::branch_count
+    pub fn total_count(&self) -> usize {
+        self.branch_count() + 1
+    }
+
+    /// Caps the number of NON-ROOT nodes in the graph, or lifts the cap with `None`, which is the
+    /// default. Once the graph is full, anything which would add nodes, like [attach], returns
+    /// [SceneGraphError::CapacityExceeded] instead, without adding any.
+    ///
+    /// Lowering the cap below the current [len] doesn't remove anything, but nothing more can be
+    /// added until the graph shrinks. This is useful for guarding against malicious input.
+    ///
+    /// Note: [attach_at_root] has nowhere to return an error, so it *panics* on a full graph. Once
+    /// a cap is set, use [try_attach_at_root] instead wherever the cap could be reached.
+    ///
+    /// [attach]: Self::attach
+    /// [attach_at_root]: Self::attach_at_root
+    /// [try_attach_at_root]: Self::try_attach_at_root
+    /// [len]: Self::len
+    pub fn set_max_nodes(&mut self, max: Option<usize>) {
+        self.max_nodes = max;
+    }
+
+    /// Returns the cap set by [set_max_nodes], if there is one.
+    ///
+    /// [set_max_nodes]: Self::set_max_nodes
+    pub fn max_nodes(&self) -> Option<usize> {
+        self.max_nodes
+    }
+
     /// Attaches a node to the root node, returning a handle to it.
     ///
-    /// This is a convenience method which will never fail.
+    /// This is a convenience method which can only fail if a cap was set with [set_max_nodes].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graph is already holding as many nodes as [set_max_nodes] allows. Use
+    /// [try_attach_at_root] to get an error instead.
+    ///
+    /// [set_max_nodes]: Self::set_max_nodes
+    /// [try_attach_at_root]: Self::try_attach_at_root
     pub fn attach_at_root(&mut self, value: T) -> NodeIndex {
-        self.attach(NodeIndex::Root, value).unwrap()
+        self.try_attach_at_root(value).expect("graph is at capacity")
+    }
+
+    /// Attaches a node to the root node, returning a handle to it. This is [attach_at_root] for
+    /// graphs with a cap set by [set_max_nodes]: if the graph is full, this returns
+    /// [SceneGraphError::CapacityExceeded] instead of panicking.
+    ///
+    /// [attach_at_root]: Self::attach_at_root
+    /// [set_max_nodes]: Self::set_max_nodes
+    pub fn try_attach_at_root(&mut self, value: T) -> Result<NodeIndex, SceneGraphError> {
+        self.attach(NodeIndex::Root, value)
     }
 
     /// Attaches a node to another node, returning a handle to it.
-    pub fn attach(&mut self, parent: NodeIndex, value: T) -> Result<NodeIndex, ParentNodeNotFound> {
+    ///
+    /// If `parent` is a stale index, this returns [SceneGraphError::ParentNodeNotFound] and
+    /// nothing is added.
+    pub fn attach(&mut self, parent: NodeIndex, value: T) -> Result<NodeIndex, SceneGraphError> {
+        if !self.contains(parent) {
+            return Err(SceneGraphError::ParentNodeNotFound);
+        }
+        self.check_capacity(1)?;
+
         // push that node!
         let new_idx = self.arena.insert(Node::new(value, parent));
-        self.place_node(parent, new_idx)?;
+        self.place_node(parent, new_idx).expect("we checked earlier");
+
+        Ok(NodeIndex::Branch(new_idx))
+    }
+
+    /// Attaches a node to another node as its `position`th child, returning a handle to it. A
+    /// `position` of `0` makes it the first child, and a `position` past the end of the children
+    /// makes it the last, just like [attach].
+    ///
+    /// [attach]: Self::attach
+    pub fn attach_at(&mut self, parent: NodeIndex, value: T, position: usize) -> Result<NodeIndex, SceneGraphError> {
+        if !self.contains(parent) {
+            return Err(SceneGraphError::ParentNodeNotFound);
+        }
+        self.check_capacity(1)?;
+
+        let new_idx = self.arena.insert(Node::new(value, parent));
+        self.place_node_at(parent, new_idx, position);
 
         Ok(NodeIndex::Branch(new_idx))
     }
 
     /// Attaches an entire scene graph to a place on this graph. The old root node will be at
     /// the returned NodeIndex.
+    ///
+    /// If the whole graph doesn't fit under the cap set by [set_max_nodes], none of it is
+    /// attached.
+    ///
+    /// [set_max_nodes]: Self::set_max_nodes
     pub fn attach_graph(
         &mut self,
         parent: NodeIndex,
         mut other_graph: SceneGraph<T>,
-    ) -> Result<(NodeIndex, HashMap<NodeIndex, NodeIndex>), ParentNodeNotFound> {
+    ) -> Result<(NodeIndex, HashMap<NodeIndex, NodeIndex>), SceneGraphError> {
+        self.check_capacity(other_graph.len() + 1)?;
+
         let other_root = other_graph.root;
         let new_root_idx = self.attach(parent, other_root)?;
 
@@ -118,9 +365,103 @@ impl<T> SceneGraph<T> {
         Ok((new_root_idx, helper_map))
     }
 
+    /// Attaches an entire scene graph, such as a prefab, as the last child of `parent`, returning
+    /// the new index of its old root. This is [attach_graph] for when the mapping from the other
+    /// graph's indices isn't needed. To choose where among the children it lands, use [graft_at].
+    ///
+    /// If the parent doesn't exist, or the whole graph doesn't fit under the cap set by
+    /// [set_max_nodes], none of it is attached.
+    ///
+    /// [attach_graph]: Self::attach_graph
+    /// [graft_at]: Self::graft_at
+    /// [set_max_nodes]: Self::set_max_nodes
+    pub fn graft(&mut self, parent: NodeIndex, other_graph: SceneGraph<T>) -> Result<NodeIndex, SceneGraphError> {
+        self.attach_graph(parent, other_graph).map(|(new_root_idx, _)| new_root_idx)
+    }
+
+    /// Attaches an entire scene graph like [attach_graph], but as the `position`th child of
+    /// `parent`, so a `position` of `0` makes it the first child. If `position` is past the end of
+    /// the children, the graph is placed last. The old root node will be at the returned
+    /// NodeIndex.
+    ///
+    /// If the parent doesn't exist, or the whole graph doesn't fit under the cap set by
+    /// [set_max_nodes], none of it is attached.
+    ///
+    /// [attach_graph]: Self::attach_graph
+    /// [set_max_nodes]: Self::set_max_nodes
+    pub fn graft_at(
+        &mut self,
+        parent: NodeIndex,
+        position: usize,
+        other_graph: SceneGraph<T>,
+    ) -> Result<NodeIndex, SceneGraphError> {
+        if !self.contains(parent) {
+            return Err(SceneGraphError::ParentNodeNotFound);
+        }
+        self.check_capacity(other_graph.len() + 1)?;
+
+        Ok(self.attach_graph_at(parent, position, other_graph))
+    }
+
+    /// Attaches each of the children of another graph's root under `parent`, in order, along with
+    /// their descendants. The other graph's root value is dropped. Unlike `attach_graph`, this
+    /// means the other graph's top level joins `parent`'s children directly.
+    ///
+    /// The new indices of the attached top-level children are returned, in order.
+    pub fn merge_root_children(
+        &mut self,
+        parent: NodeIndex,
+        mut other_graph: SceneGraph<T>,
+    ) -> Result<Vec<NodeIndex>, SceneGraphError> {
+        if !self.contains(parent) {
+            return Err(SceneGraphError::ParentNodeNotFound);
+        }
+        self.check_capacity(other_graph.len())?;
+
+        let mut top_level = Vec::new();
+        let mut helper_map = HashMap::new();
+        helper_map.insert(NodeIndex::Root, parent);
+
+        for detached_node in other_graph.iter_detach_from_root() {
+            let parent_place = helper_map[&detached_node.parent_idx];
+            let new_idx = self.attach(parent_place, detached_node.node_value).unwrap();
+
+            if detached_node.parent_idx.is_root() {
+                top_level.push(new_idx);
+            }
+            helper_map.insert(detached_node.node_idx, new_idx);
+        }
+
+        Ok(top_level)
+    }
+
+    /// Clones a given node and all of its descendants into a new SceneGraph, where the copy of the
+    /// given node is the *root*. Passing the Root clones the whole graph. Sibling order is kept,
+    /// but the new graph has its own arena, so indices from this graph are not valid on it. This
+    /// graph is left as it was.
+    ///
+    /// Note: this returns `None` when the node doesn't exist.
+    pub fn clone_subtree(&self, node_index: NodeIndex) -> Option<SceneGraph<T>>
+    where
+        T: Clone,
+    {
+        let mut sg = SceneGraph::new(self.value(node_index)?.clone());
+        let mut new_indices = HashMap::from([(node_index, NodeIndex::Root)]);
+
+        for visit in SceneGraphIterDetailed::new(self, node_index) {
+            let new_idx = sg.attach(new_indices[&visit.parent], visit.value.clone()).unwrap();
+            new_indices.insert(visit.index, new_idx);
+        }
+
+        Some(sg)
+    }
+
     /// Removes a given node from the scene graph, returning a new SceneGraph where the given
     /// node is now the *root*.
     ///
+    /// Every node in the subtree is freed from this graph's arena, so their old indices are no
+    /// longer valid here. To drop the subtree instead, use `remove`.
+    ///
     /// Note: this always returns `None` when the node doesn't exist, or when the `node_index` is
     /// the Root.
     pub fn detach(&mut self, node_index: NodeIndex) -> Option<SceneGraph<T>> {
@@ -136,8 +477,6 @@ impl<T> SceneGraph<T> {
         helper_map.insert(NodeIndex::Branch(node_index), NodeIndex::Root);
 
         for detached_node in SceneGraphDetachIter::new(&mut self.arena, NodeIndex::Branch(node_index), node.children) {
-            println!("detached_node = {:#?}", detached_node);
-
             let parent_place = match detached_node.parent_idx {
                 NodeIndex::Root => NodeIndex::Root,
                 NodeIndex::Branch(_) => *helper_map.get(&detached_node.parent_idx).unwrap(),
@@ -152,6 +491,51 @@ impl<T> SceneGraph<T> {
         Some(new_sg)
     }
 
+    /// Removes a given node from the scene graph like [detach], but also remembers where it was,
+    /// so that it can be put back with [reattach].
+    ///
+    /// Note: this always returns `None` when the node doesn't exist, or when the `node_index` is
+    /// the Root.
+    ///
+    /// [detach]: Self::detach
+    /// [reattach]: Self::reattach
+    pub fn detach_subtree(&mut self, node_index: NodeIndex) -> Option<DetachedSubtree<T>> {
+        let NodeIndex::Branch(idx) = node_index else {
+            return None;
+        };
+
+        let parent = self.arena.get(idx)?.parent;
+        let position = self.sibling_position(idx);
+        let graph = self.detach(node_index)?;
+
+        Some(DetachedSubtree { graph, parent, position })
+    }
+
+    /// Puts a subtree taken by [detach_subtree] back where it came from, at the same position
+    /// among its siblings. If some of those siblings have since been removed, it is placed as
+    /// close to its old position as it can be, and if it was last, it is placed last.
+    ///
+    /// The subtree is attached fresh, so its nodes will *not* have their old indices back. The new
+    /// index of the subtree's root is returned. If the old parent no longer exists, or the
+    /// subtree no longer fits under the cap set by [set_max_nodes], this returns a
+    /// [ReattachError] holding the subtree, untouched, so nothing is lost.
+    ///
+    /// [detach_subtree]: Self::detach_subtree
+    /// [set_max_nodes]: Self::set_max_nodes
+    pub fn reattach(&mut self, detached: DetachedSubtree<T>) -> Result<NodeIndex, ReattachError<T>> {
+        if !self.contains(detached.parent) {
+            return Err(ReattachError {
+                error: SceneGraphError::ParentNodeNotFound,
+                detached,
+            });
+        }
+        if let Err(error) = self.check_capacity(detached.graph.len() + 1) {
+            return Err(ReattachError { error, detached });
+        }
+
+        Ok(self.attach_graph_at(detached.parent, detached.position, detached.graph))
+    }
+
     /// Moves a node from one parent to another parent. If this operation returns `Err`, then
     /// nothing will have happened to the node.
     pub fn move_node(&mut self, moving_node_idx: NodeIndex, new_parent: NodeIndex) -> Result<(), NodeDoesNotExist> {
@@ -172,17 +556,9 @@ impl<T> SceneGraph<T> {
             }
         }
 
-        // okay, now we hot swap em
-        let moving_node = self.arena.get_mut(moving_node_idx).expect("we checked earlier");
-        let old_parent = moving_node.parent;
-        moving_node.parent = new_parent;
-
-        let next_sibling = moving_node.next_sibling;
-        moving_node.next_sibling = None;
-        let last_sibling = moving_node.last_sibling;
-
-        // now let's fix our old dad
-        self.fix_parent(next_sibling, last_sibling, old_parent, moving_node_idx);
+        // okay, now we hot swap em, fixing our old dad first
+        self.unlink(moving_node_idx);
+        self.arena[moving_node_idx].parent = new_parent;
 
         // place it!
         self.place_node(new_parent, moving_node_idx)
@@ -191,6 +567,95 @@ impl<T> SceneGraph<T> {
         Ok(())
     }
 
+    /// Swaps the subtree at `node_index` in `self` with the subtree at `other_index` in `other`.
+    /// Each subtree takes the other's place, including its position among its new siblings.
+    ///
+    /// Because the nodes move between arenas, every index within both subtrees is invalidated. The
+    /// new indices of the two subtree roots are returned, first the one now in `self` and then the
+    /// one now in `other`. If this operation returns `Err`, neither graph has been changed. This
+    /// fails if either graph would grow past the cap set by [set_max_nodes].
+    ///
+    /// Note: this always returns `Err` if either index is the Root.
+    ///
+    /// [set_max_nodes]: Self::set_max_nodes
+    pub fn swap_subtree_with(
+        &mut self,
+        node_index: NodeIndex,
+        other: &mut SceneGraph<T>,
+        other_index: NodeIndex,
+    ) -> Result<(NodeIndex, NodeIndex), SceneGraphError> {
+        let (NodeIndex::Branch(idx), NodeIndex::Branch(other_idx)) = (node_index, other_index) else {
+            return Err(SceneGraphError::NodeDoesNotExist);
+        };
+
+        if !self.arena.contains(idx) || !other.arena.contains(other_idx) {
+            return Err(SceneGraphError::NodeDoesNotExist);
+        }
+
+        let size = SceneGraphIterDetailed::new(self, node_index).count() + 1;
+        let other_size = SceneGraphIterDetailed::new(other, other_index).count() + 1;
+        self.check_capacity(other_size.saturating_sub(size))?;
+        other.check_capacity(size.saturating_sub(other_size))?;
+
+        let parent = self.arena[idx].parent;
+        let position = self.sibling_position(idx);
+        let other_parent = other.arena[other_idx].parent;
+        let other_position = other.sibling_position(other_idx);
+
+        let subtree = self.detach(node_index).expect("we checked earlier");
+        let other_subtree = other.detach(other_index).expect("we checked earlier");
+
+        let new_idx = self.attach_graph_at(parent, position, other_subtree);
+        let other_new_idx = other.attach_graph_at(other_parent, other_position, subtree);
+
+        Ok((new_idx, other_new_idx))
+    }
+
+    /// Moves a node to be the `position`th child of `new_parent`, where `0` is the first child. If
+    /// `position` is past the end of `new_parent`'s children, the node is placed last. The node
+    /// brings all of its descendants along with it.
+    ///
+    /// This fails if the node would be moved beneath itself or one of its descendants. If this
+    /// operation returns `Err`, then nothing will have happened to the node.
+    pub fn move_node_as_child_at(
+        &mut self,
+        moving_node_idx: NodeIndex,
+        new_parent: NodeIndex,
+        position: usize,
+    ) -> Result<(), SceneGraphError> {
+        let NodeIndex::Branch(moving_node_idx) = moving_node_idx else {
+            return Err(SceneGraphError::NodeDoesNotExist);
+        };
+
+        if !self.arena.contains(moving_node_idx) || !self.contains(new_parent) {
+            return Err(SceneGraphError::NodeDoesNotExist);
+        }
+
+        if self.is_self_or_ancestor(moving_node_idx, new_parent) {
+            return Err(SceneGraphError::WouldCycle);
+        }
+
+        self.unlink(moving_node_idx);
+        self.place_node_at(new_parent, moving_node_idx, position);
+
+        Ok(())
+    }
+
+    /// Moves a node to be the last child of `new_parent`, bringing all of its descendants along
+    /// with it. Only links are changed, so every index in the subtree stays valid and nothing is
+    /// cloned.
+    ///
+    /// Unlike [move_node], this fails if the node would be moved beneath itself or one of its
+    /// descendants. The Root can never be reparented. If this operation returns `Err`, then
+    /// nothing will have happened to the node. This is [move_node_as_child_at], always placing the
+    /// node last.
+    ///
+    /// [move_node]: Self::move_node
+    /// [move_node_as_child_at]: Self::move_node_as_child_at
+    pub fn reparent(&mut self, node_index: NodeIndex, new_parent: NodeIndex) -> Result<(), SceneGraphError> {
+        self.move_node_as_child_at(node_index, new_parent, usize::MAX)
+    }
+
     /// Removes a node *without* returning anything. This can save a few allocations. This removes
     /// all of its children as well.
     pub fn remove(&mut self, node_index: NodeIndex) {
@@ -199,530 +664,5064 @@ impl<T> SceneGraph<T> {
             NodeIndex::Branch(index) => index,
         };
 
-        let Some(node) = self.arena.remove(index) else { return };
-
-        // detach em all!
-        for _v in SceneGraphDetachIter::new(&mut self.arena, node_index, node.children) {}
-
-        self.fix_parent(node.next_sibling, node.last_sibling, node.parent, index);
-    }
-
-    /// Returns `true` is the given `node_index` is valid.
-    pub fn contains(&self, node_index: NodeIndex) -> bool {
-        match node_index {
-            NodeIndex::Root => true,
-            NodeIndex::Branch(idx) => self.arena.contains(idx),
+        if self.arena.contains(index) {
+            self.remove_subtree(index);
         }
     }
 
-    /// Gets a given node based on `NodeIndex`. Note that the `Root` always returns `None`.
-    /// Simply access `root_value` to get the root value.
-    pub fn get(&self, node_index: NodeIndex) -> Option<&Node<T>> {
-        match node_index {
-            NodeIndex::Root => None,
-            NodeIndex::Branch(idx) => self.arena.get(idx),
+    /// Removes a node and all of its descendants, like `remove`, but fills the hole it leaves
+    /// with its parent's last child, similar to [Vec::swap_remove]. This returns the removed
+    /// node's value, dropping the values of its descendants.
+    ///
+    /// Use this when the order of siblings doesn't matter. If this operation returns `Err`, then
+    /// nothing will have happened to the node.
+    ///
+    /// Note: this always returns `Err` when the `node_index` is the Root.
+    pub fn swap_remove_child(&mut self, node_index: NodeIndex) -> Result<T, NodeDoesNotExist> {
+        let NodeIndex::Branch(idx) = node_index else {
+            return Err(NodeDoesNotExist);
+        };
+
+        let parent = self.arena.get(idx).ok_or(NodeDoesNotExist)?.parent;
+        let last_child = match parent {
+            NodeIndex::Root => self.root_children,
+            NodeIndex::Branch(parent) => self.arena[parent].children,
         }
-    }
+        .unwrap()
+        .last;
 
-    /// Gets a given node based on `NodeIndex`. Note that the `Root` always returns `None`,
-    /// as it is not a true node. Use `get_children` to generically get children.
-    pub fn get_mut(&mut self, node_index: NodeIndex) -> Option<&mut Node<T>> {
-        match node_index {
-            NodeIndex::Root => None,
-            NodeIndex::Branch(idx) => self.arena.get_mut(idx),
+        if last_child != idx {
+            self.unlink(last_child);
+            self.place_node_before(idx, last_child);
         }
-    }
 
-    /// Gets the root node's value.
-    pub fn root(&self) -> &T {
-        &self.root
+        Ok(self.remove_subtree(idx))
     }
 
-    /// Gets the root node's value mutably.
-    pub fn root_mut(&mut self) -> &mut T {
-        &mut self.root
+    /// Removes every node whose value matches the predicate, along with all of its descendants,
+    /// returning the removed values.
+    ///
+    /// Matching nodes are found in a depth first traversal, and each matching node's value is
+    /// followed by the values of its descendants, also depth first. Descendants of a matching node
+    /// are removed whether or not they match. The root is never removed.
+    pub fn remove_matching(&mut self, mut predicate: impl FnMut(&T) -> bool) -> Vec<T> {
+        let mut removed = Vec::new();
+
+        for idx in self.topmost_matching(|_, value| predicate(value)) {
+            let node = self.arena.remove(idx).expect("we just found it");
+            removed.push(node.value);
+
+            let detach_iter = SceneGraphDetachIter::new(&mut self.arena, NodeIndex::Branch(idx), node.children);
+            removed.extend(detach_iter.map(|detached_node| detached_node.node_value));
+
+            self.fix_parent(node.next_sibling, node.last_sibling, node.parent, idx);
+        }
+
+        removed
     }
 
-    /// Returns the parent NodeIndex of a given Node.
+    /// Keeps only the nodes whose value fulfills `keep`, and removes every other node in place,
+    /// freeing its slot. Removing a node always removes its whole subtree, so a descendant of a
+    /// removed node is removed even if it would have been kept, and is never given to `keep`. The
+    /// root is never removed, nor given to `keep`.
     ///
-    /// This operation is O1 over the number of nodes in the SceneGraph.
-    /// Note: this returns `None` for the Root.
-    pub fn parent(&self, node_index: NodeIndex) -> Option<NodeIndex> {
-        self.get(node_index).map(|v| v.parent)
+    /// To decide based on each node's index as well, use [retain_indexed].
+    ///
+    /// [retain_indexed]: Self::retain_indexed
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        self.retain_indexed(|_, value| keep(value));
     }
 
-    /// Iterate mutably over the Scene Graph in a depth first traversal.
-    pub fn iter_mut(&mut self) -> SceneGraphIterMut<'_, T> {
-        SceneGraphIterMut::new(self, NodeIndex::Root)
+    /// Keeps only the nodes for which `keep` returns `true`, given each node's index and value, and
+    /// removes every other node along with all of its descendants. The descendants of a removed
+    /// node are never given to `keep`. The root is never removed, nor given to `keep`.
+    pub fn retain_indexed(&mut self, mut keep: impl FnMut(NodeIndex, &T) -> bool) {
+        for idx in self.topmost_matching(|node_index, value| !keep(node_index, value)) {
+            self.remove_subtree(idx);
+        }
     }
 
-    /// Iterate mutably over the Scene Graph in a depth first traversal, skipping branches/subtrees where
-    /// a child does not fulfill the predicate.
-    pub fn iter_mut_predicate(&mut self, predicate: fn(&T) -> bool) -> SceneGraphIterMutPredicate<'_, T> {
-        SceneGraphIterMutPredicate::new(self, NodeIndex::Root, predicate)
-    }
+    /// Keeps only the `n` nodes with the highest `importance`, along with all of their ancestors so
+    /// that the graph stays connected, and removes everything else. Ties are broken in favor of
+    /// whichever node comes first in a depth first traversal. The root is always kept, and is not
+    /// counted as one of the `n`.
+    pub fn keep_top_n(&mut self, n: usize, importance: impl Fn(&T) -> i64) {
+        let mut ranked = Vec::from_iter(self.iter_detailed().map(|visit| (visit.index, importance(visit.value))));
+        ranked.sort_by_key(|&(_, importance)| std::cmp::Reverse(importance));
 
-    /// Iterate immutably over the Scene Graph in a depth first traversal.
-    pub fn iter(&self) -> SceneGraphIter<'_, T> {
-        self.iter_from_node(NodeIndex::Root).unwrap()
-    }
+        let mut kept = HashSet::with_capacity(n);
+        for &(mut node_index, _) in ranked.iter().take(n) {
+            // walk up until we find someone who's already in
+            while let NodeIndex::Branch(idx) = node_index {
+                if !kept.insert(node_index) {
+                    break;
+                }
 
-    /// Iterate immutably over the Scene Graph out of order. This is useful for speed.
-    pub fn iter_out_of_order(&self) -> impl Iterator<Item = (NodeIndex, &T)> {
-        self.arena.iter().map(|(k, v)| (NodeIndex::Branch(k), &v.value))
-    }
+                node_index = self.arena[idx].parent;
+            }
+        }
 
-    /// Iterate mutably over the Scene Graph out of order. This is useful for speed.
-    pub fn iter_out_of_order_mut(&mut self) -> impl Iterator<Item = (NodeIndex, &mut T)> {
-        self.arena.iter_mut().map(|(k, v)| (NodeIndex::Branch(k), &mut v.value))
+        for idx in self.topmost_matching(|node_index, _| !kept.contains(&node_index)) {
+            self.remove_subtree(idx);
+        }
     }
 
-    /// Iterate immutably over the Scene Graph in a depth first traversal.
-    pub fn iter_from_node(&self, node_index: NodeIndex) -> Result<SceneGraphIter<'_, T>, NodeDoesNotExist> {
-        let (parent_value, children) = match node_index {
-            NodeIndex::Root => (&self.root, self.root_children.as_ref()),
-            NodeIndex::Branch(idx) => {
-                let node = self.arena.get(idx).ok_or(NodeDoesNotExist)?;
+    /// Keeps only the nodes whose depth is between `min_depth` and `max_depth`, inclusive, where
+    /// the children of the root have a depth of `1`. Everything deeper than `max_depth` is
+    /// removed.
+    ///
+    /// Nodes shallower than `min_depth` are kept as connectors, so that the surviving nodes stay
+    /// attached to the root, but only if they have at least one descendant within the band. A
+    /// shallow subtree which never reaches `min_depth` is removed entirely. The root is always
+    /// kept. If `min_depth` is greater than `max_depth`, the band is empty, so every node is
+    /// removed.
+    pub fn keep_depth_range(&mut self, min_depth: usize, max_depth: usize) {
+        // the deepest depth found in each subtree
+        let mut reaches: HashMap<NodeIndex, usize> = HashMap::with_capacity(self.len());
+        let mut depths = HashMap::with_capacity(self.len());
 
-                (&node.value, node.children.as_ref())
-            }
-        };
+        let visits = Vec::from_iter(self.iter_detailed());
+        for visit in visits.iter().rev() {
+            let reach = reaches.get(&visit.index).copied().unwrap_or(0).max(visit.depth);
+            reaches.insert(visit.index, reach);
 
-        Ok(SceneGraphIter::new(self, parent_value, children))
+            let parent_reach = reaches.entry(visit.parent).or_insert(0);
+            *parent_reach = (*parent_reach).max(reach);
+
+            depths.insert(visit.index, visit.depth);
+        }
+
+        let pruned = self.topmost_matching(|node_index, _| {
+            depths[&node_index] > max_depth || reaches[&node_index] < min_depth || min_depth > max_depth
+        });
+        for idx in pruned {
+            self.remove_subtree(idx);
+        }
     }
 
-    /// Iterate mutably over the Scene Graph in a depth first traversal.
-    pub fn iter_mut_from_node(&mut self, node_index: NodeIndex) -> Result<SceneGraphIterMut<'_, T>, NodeDoesNotExist> {
-        match node_index {
-            NodeIndex::Root => {}
-            NodeIndex::Branch(idx) => {
-                if !self.arena.contains(idx) {
-                    return Err(NodeDoesNotExist);
-                }
-            }
+    /// Removes a node which has no children, returning its value. This is cheaper than `remove`,
+    /// since only one node has to be freed.
+    ///
+    /// This fails if the node has any children, or if it is the Root. If this operation returns
+    /// `Err`, then nothing will have happened to the node.
+    pub fn remove_leaf(&mut self, node_index: NodeIndex) -> Result<T, SceneGraphError> {
+        let NodeIndex::Branch(idx) = node_index else {
+            return Err(SceneGraphError::NodeDoesNotExist);
         };
 
-        Ok(SceneGraphIterMut::new(self, node_index))
+        let node = self.arena.get(idx).ok_or(SceneGraphError::NodeDoesNotExist)?;
+        if node.has_children() {
+            return Err(SceneGraphError::HasChildren);
+        }
+
+        self.unlink(idx);
+
+        Ok(self.arena.remove(idx).unwrap().value)
     }
 
-    /// Iterate while detaching over the Scene Graph in a depth first traversal.
+    /// Replaces a node which has exactly one child with that child. The child takes the node's
+    /// place among its siblings, bringing its own descendants along, and the node's value is
+    /// handed to `merge` along with the child's value, so it can be folded in or simply dropped.
     ///
-    /// Note: the `root` will never be detached.
-    pub fn iter_detach_from_root(&mut self) -> SceneGraphDetachIter<'_, T> {
-        SceneGraphDetachIter::new(&mut self.arena, NodeIndex::Root, self.root_children.take())
-    }
+    /// Returns the index of the surviving child, which is unchanged. If this operation returns
+    /// `Err`, then nothing will have happened to the node.
+    pub fn collapse_into_only_child(
+        &mut self,
+        node_index: NodeIndex,
+        merge: impl FnOnce(T, &mut T),
+    ) -> Result<NodeIndex, SceneGraphError> {
+        let NodeIndex::Branch(idx) = node_index else {
+            return Err(SceneGraphError::NodeDoesNotExist);
+        };
 
-    /// Iterate while detaching over the Scene Graph in a depth first traversal.
-    /// This leaves the `node_index` given in the graph, but removes all its descendents.
-    pub fn iter_detach(&mut self, node_index: NodeIndex) -> Result<SceneGraphDetachIter<'_, T>, NodeDoesNotExist> {
-        let children = match node_index {
-            NodeIndex::Root => self.root_children.take(),
-            NodeIndex::Branch(br) => match self.arena.get_mut(br) {
-                Some(v) => v.children.take(),
-                None => return Err(NodeDoesNotExist),
-            },
+        let node = self.arena.get(idx).ok_or(SceneGraphError::NodeDoesNotExist)?;
+        let child = match node.children {
+            Some(children) if children.count == 1 => children.first,
+            _ => return Err(SceneGraphError::NotExactlyOneChild),
         };
 
-        Ok(SceneGraphDetachIter::new(&mut self.arena, node_index, children))
+        // slot the child in right where we are, and then we're just a leaf
+        self.unlink(child);
+        self.place_node_before(idx, child);
+        let value = self.remove_subtree(idx);
+
+        merge(value, &mut self.arena[child].value);
+
+        Ok(NodeIndex::Branch(child))
     }
 
-    /// Iterate directly over only the *direct* children of `parent_index`.
+    /// Removes a node, but keeps its *direct* children, which take its place among its siblings,
+    /// in order, bringing their own descendants along. This is an "ungroup". The node's value is
+    /// dropped.
     ///
-    /// For example, given a graph:
-    /// ROOT:
-    ///     A
-    ///         B
-    ///         C
-    ///             D
-    /// using [iter_direct_children] and passing in the `parent_index` for `A` will only yield `B`
-    /// and `C`, *not* `D`. For that kind of depth first traversal, using `iter_on_node`.
+    /// The promoted children keep their indices, which are returned in order. If this operation
+    /// returns `Err`, then nothing will have happened to the node.
     ///
-    /// [iter_direct_children]: [Self::iter_direct_children]
-    pub fn iter_direct_children(
-        &self,
-        parent_index: NodeIndex,
-    ) -> Result<SceneGraphChildIter<'_, T>, NodeDoesNotExist> {
-        if let NodeIndex::Branch(idx) = parent_index {
-            self.arena.get(idx).ok_or(NodeDoesNotExist)?;
+    /// Note: this always returns `Err` when the `node_index` is the Root.
+    pub fn dissolve(&mut self, node_index: NodeIndex) -> Result<Vec<NodeIndex>, NodeDoesNotExist> {
+        let NodeIndex::Branch(idx) = node_index else {
+            return Err(NodeDoesNotExist);
+        };
+
+        let children = self.arena.get(idx).ok_or(NodeDoesNotExist)?.children;
+        let promoted = Vec::from_iter(self.child_indices(children));
+
+        // each child slots in ahead of us, so they stay in order
+        for &child in &promoted {
+            self.unlink(child);
+            self.place_node_before(idx, child);
         }
+        self.remove_subtree(idx);
 
-        Ok(SceneGraphChildIter::new(self, parent_index))
+        Ok(promoted.into_iter().map(NodeIndex::Branch).collect())
     }
 
-    /// Applies a function on the given node and all its direct ancestors.
-    pub fn for_each_upward(&mut self, starting_node: NodeIndex, function: fn(&mut T)) {
-        let mut current_node = Some(starting_node);
-        while let Some(node) = current_node {
-            if let NodeIndex::Branch(idx) = node {
-                current_node = self.arena.get_mut(idx)
-                    .map(|n| {
-                        function(&mut n.value);
-                        n.parent
-                    });
-            } else {
-                function(&mut self.root);
-                current_node = None;
-            }
+    /// Removes everything in the graph except the subtree at `node_index`, which becomes the only
+    /// child of the root. The root's value is untouched, and every index within the kept subtree
+    /// stays valid.
+    ///
+    /// Isolating the Root does nothing. If this operation returns `Err`, then nothing will have
+    /// happened to the graph.
+    pub fn isolate(&mut self, node_index: NodeIndex) -> Result<(), NodeDoesNotExist> {
+        let idx = match node_index {
+            NodeIndex::Root => return Ok(()),
+            NodeIndex::Branch(idx) => idx,
+        };
+
+        if !self.arena.contains(idx) {
+            return Err(NodeDoesNotExist);
         }
+
+        // once we're unlinked, we're not caught up in the detaching
+        self.unlink(idx);
+        for _v in self.iter_detach_from_root() {}
+
+        self.place_node(NodeIndex::Root, idx).expect("root always exists");
+        self.arena[idx].parent = NodeIndex::Root;
+
+        Ok(())
     }
 
-    /// Places a node as part of moving or attaching it.
-    fn place_node(&mut self, new_parent: NodeIndex, node_to_place: Index) -> Result<(), ParentNodeNotFound> {
-        // okay, now we gotta ATTACH ourselves back, without being monsters about it
-        let parent_children = match new_parent {
-            NodeIndex::Root => &mut self.root_children,
-            NodeIndex::Branch(idx) => &mut self.arena.get_mut(idx).ok_or(ParentNodeNotFound)?.children,
+    /// Scans the *direct* children of `parent_index` from first to last, merging each child into
+    /// the sibling before it whenever `should_merge(previous, current)` is `true`. The merged
+    /// child's value is handed to `merge`, and its children are moved, in order, to the end of
+    /// the previous sibling's children.
+    ///
+    /// Merged children are removed, so their indices are invalidated. A run of mergeable siblings
+    /// collapses into its first sibling, which is compared against each sibling after merging.
+    pub fn coalesce_children(
+        &mut self,
+        parent_index: NodeIndex,
+        should_merge: impl Fn(&T, &T) -> bool,
+        merge: impl Fn(&mut T, T),
+    ) -> Result<(), NodeDoesNotExist> {
+        let children = match parent_index {
+            NodeIndex::Root => self.root_children,
+            NodeIndex::Branch(idx) => self.arena.get(idx).ok_or(NodeDoesNotExist)?.children,
         };
 
-        // slap ourselves in here
-        match parent_children.as_mut() {
-            Some(children) => {
-                let old_last = children.last;
-                children.last = node_to_place;
-
-                let mut last_sibling = &mut self.arena[old_last];
-                last_sibling.next_sibling = Some(node_to_place);
+        let Some(mut previous) = children.map(|v| v.first) else {
+            return Ok(());
+        };
 
-                // fix this up too
-                self.arena[node_to_place].last_sibling = Some(old_last);
+        while let Some(current) = self.arena[previous].next_sibling {
+            if !should_merge(&self.arena[previous].value, &self.arena[current].value) {
+                previous = current;
+                continue;
             }
-            None => {
-                // this is the easy case
-                *parent_children = Some(Children {
-                    first: node_to_place,
-                    last: node_to_place,
-                });
+
+            // hand the grandchildren over before we go
+            let grandchildren = Vec::from_iter(self.child_indices(self.arena[current].children));
+            for grandchild in grandchildren {
+                self.unlink(grandchild);
+                self.arena[grandchild].parent = NodeIndex::Branch(previous);
+                self.place_node(NodeIndex::Branch(previous), grandchild)
+                    .expect("we just saw it");
             }
-        };
+
+            self.unlink(current);
+            let value = self.arena.remove(current).unwrap().value;
+
+            merge(&mut self.arena[previous].value, value);
+        }
 
         Ok(())
     }
 
-    /// Fixes a parent with a removed child.
-    fn fix_parent(
+    /// Splits the children of `parent_index` in two: the first `at` children stay where they are,
+    /// and the rest are moved, in order, under a new node holding `value`. The new node is placed
+    /// as the next sibling of `parent_index`, and its index is returned. If `parent_index` has `at`
+    /// children or fewer, the new node is left empty.
+    ///
+    /// Every moved child keeps its index. Note: the Root has no siblings, so this always returns
+    /// `Err` for it.
+    pub fn split_children_at(
         &mut self,
-        removed_next_sibling: Option<Index>,
-        removed_last_sibling: Option<Index>,
-        removed_parent: NodeIndex,
-        removed_idx: Index,
-    ) {
-        // fix up the parent if it was the first child...
+        parent_index: NodeIndex,
+        at: usize,
+        value: T,
+    ) -> Result<NodeIndex, SceneGraphError> {
+        let NodeIndex::Branch(parent_idx) = parent_index else {
+            return Err(SceneGraphError::NodeDoesNotExist);
+        };
 
-        let mut parent_children = match removed_parent {
-            NodeIndex::Root => self.root_children.unwrap(),
-            NodeIndex::Branch(idx) => self.arena[idx].children.unwrap(),
+        let parent = self.arena.get(parent_idx).ok_or(SceneGraphError::NodeDoesNotExist)?;
+        let (grandparent, next_sibling, children) = (parent.parent, parent.next_sibling, parent.children);
+
+        self.check_capacity(1)?;
+        let group = self.arena.insert(Node::new(value, grandparent));
+        match next_sibling {
+            Some(next_sibling) => self.place_node_before(next_sibling, group),
+            None => self.place_node(grandparent, group).expect("grandparent must exist"),
+        }
+
+        let moving = Vec::from_iter(self.child_indices(children).skip(at));
+        for idx in moving {
+            self.unlink(idx);
+            self.arena[idx].parent = NodeIndex::Branch(group);
+            self.place_node(NodeIndex::Branch(group), idx).expect("we just made it");
+        }
+
+        Ok(NodeIndex::Branch(group))
+    }
+
+    /// Reorders the children of every node, including the root, so that they are in descending
+    /// order of `weight`. The sort is stable, so children with equal weights keep their order.
+    ///
+    /// No nodes change parents, so every index stays valid.
+    pub fn order_children_by_weight(&mut self, weight: impl Fn(&T) -> u64) {
+        let parents = std::iter::once(NodeIndex::Root)
+            .chain(self.arena.iter().filter(|(_, node)| node.has_children()).map(|(idx, _)| NodeIndex::Branch(idx)))
+            .collect::<Vec<_>>();
+
+        let mut order = Vec::new();
+        for parent in parents {
+            let children = match parent {
+                NodeIndex::Root => self.root_children,
+                NodeIndex::Branch(idx) => self.arena[idx].children,
+            };
+
+            order.extend(self.child_indices(children));
+            order.sort_by_key(|&idx| std::cmp::Reverse(weight(&self.arena[idx].value)));
+            self.relink_children(parent, &order);
+            order.clear();
+        }
+    }
+
+    /// Reorders the direct children of `parent` to match `new_order`, which must hold exactly the
+    /// current children of `parent`, each once, in any order. No nodes change parents, so every
+    /// index stays valid.
+    ///
+    /// If `new_order` is anything other than a rearrangement of the children, this returns
+    /// [SceneGraphError::InvalidChildOrder] and nothing changes.
+    pub fn reorder_children(&mut self, parent: NodeIndex, new_order: &[NodeIndex]) -> Result<(), SceneGraphError> {
+        let children = match parent {
+            NodeIndex::Root => self.root_children,
+            NodeIndex::Branch(idx) => self.arena.get(idx).ok_or(SceneGraphError::NodeDoesNotExist)?.children,
         };
 
-        if parent_children.first == parent_children.last && parent_children.first == removed_idx {
-            match removed_parent {
-                NodeIndex::Root => self.root_children = None,
-                NodeIndex::Branch(idx) => self.arena[idx].children = None,
+        if new_order.len() != children.map_or(0, |v| v.count) {
+            return Err(SceneGraphError::InvalidChildOrder);
+        }
+
+        let mut seen = HashSet::with_capacity(new_order.len());
+        let mut order = Vec::with_capacity(new_order.len());
+        for &child in new_order {
+            let NodeIndex::Branch(idx) = child else {
+                return Err(SceneGraphError::InvalidChildOrder);
             };
-        } else {
-            // extremely hard to follow the logic of this unwrap here, but if this branch is taken,
-            // then we're *never* the last child, which means we have a sibling.
-            if parent_children.first == removed_idx {
-                parent_children.first = removed_next_sibling.unwrap();
+            let is_child = self.arena.get(idx).is_some_and(|node| node.parent == parent);
+            if !is_child || !seen.insert(idx) {
+                return Err(SceneGraphError::InvalidChildOrder);
             }
 
-            if parent_children.last == removed_idx {
-                parent_children.last = removed_last_sibling.unwrap();
+            order.push(idx);
+        }
+
+        self.relink_children(parent, &order);
+
+        Ok(())
+    }
+
+    /// Returns `true` is the given `node_index` is valid.
+    pub fn contains(&self, node_index: NodeIndex) -> bool {
+        match node_index {
+            NodeIndex::Root => true,
+            NodeIndex::Branch(idx) => self.arena.contains(idx),
+        }
+    }
+
+    /// Gets a given node based on `NodeIndex`. Note that the `Root` always returns `None`.
+    /// Simply access `root_value` to get the root value.
+    pub fn get(&self, node_index: NodeIndex) -> Option<&Node<T>> {
+        match node_index {
+            NodeIndex::Root => None,
+            NodeIndex::Branch(idx) => self.arena.get(idx),
+        }
+    }
+
+    /// Gets a given node based on `NodeIndex`. Note that the `Root` always returns `None`,
+    /// as it is not a true node. Use `get_children` to generically get children.
+    pub fn get_mut(&mut self, node_index: NodeIndex) -> Option<&mut Node<T>> {
+        match node_index {
+            NodeIndex::Root => None,
+            NodeIndex::Branch(idx) => self.arena.get_mut(idx),
+        }
+    }
+
+    /// Gets the value at a given `NodeIndex`. Unlike [get], this gives the root value for the
+    /// `Root`. A stale or otherwise invalid index returns `None`, rather than panicking, so
+    /// indices can be safely held onto across changes to the graph.
+    ///
+    /// [get]: Self::get
+    pub fn value(&self, node_index: NodeIndex) -> Option<&T> {
+        match node_index {
+            NodeIndex::Root => Some(&self.root),
+            NodeIndex::Branch(idx) => self.arena.get(idx).map(|v| &v.value),
+        }
+    }
+
+    /// Gets the value at a given `NodeIndex` mutably. Unlike [get_mut], this gives the root value
+    /// for the `Root`. A stale or otherwise invalid index returns `None`.
+    ///
+    /// [get_mut]: Self::get_mut
+    pub fn value_mut(&mut self, node_index: NodeIndex) -> Option<&mut T> {
+        match node_index {
+            NodeIndex::Root => Some(&mut self.root),
+            NodeIndex::Branch(idx) => self.arena.get_mut(idx).map(|v| &mut v.value),
+        }
+    }
+
+    /// Gets the values at several different nodes mutably, all at once, in the order given. The
+    /// Root may be included, which gives the root value.
+    ///
+    /// Up to two nodes other than the Root are looked up directly. Asking for more than that walks
+    /// the whole arena, so it is linear in the size of the graph.
+    ///
+    /// Note: this returns `None` if any index doesn't exist, or appears more than once.
+    pub fn get_disjoint_mut<const N: usize>(&mut self, indices: [NodeIndex; N]) -> Option<[&mut T; N]> {
+        for (position, node_index) in indices.iter().enumerate() {
+            if !self.contains(*node_index) || indices[..position].contains(node_index) {
+                return None;
+            }
+        }
+
+        // the root lives outside the arena, so it's a separate borrow from the arena lookups below
+        let mut values: [Option<&mut T>; N] = std::array::from_fn(|_| None);
+        if let Some(position) = indices.iter().position(|v| *v == NodeIndex::Root) {
+            values[position] = Some(&mut self.root);
+        }
+
+        let mut branches = indices.iter().enumerate().filter_map(|(position, node_index)| match node_index {
+            NodeIndex::Root => None,
+            NodeIndex::Branch(idx) => Some((position, *idx)),
+        });
+
+        match indices.iter().filter(|v| !v.is_root()).count() {
+            0 => {}
+            1 => {
+                let (position, idx) = branches.next().unwrap();
+                values[position] = Some(&mut self.arena[idx].value);
+            }
+            2 => {
+                let (a_position, a) = branches.next().unwrap();
+                let (b_position, b) = branches.next().unwrap();
+                let (a_node, b_node) = self.arena.get2_mut(a, b);
+                values[a_position] = a_node.map(|v| &mut v.value);
+                values[b_position] = b_node.map(|v| &mut v.value);
+            }
+            _ => {
+                for (idx, node) in self.arena.iter_mut() {
+                    if let Some(position) = indices.iter().position(|v| *v == NodeIndex::Branch(idx)) {
+                        values[position] = Some(&mut node.value);
+                    }
+                }
+            }
+        }
+
+        // every index was checked to exist above, so each slot got filled
+        Some(values.map(|v| v.unwrap()))
+    }
+
+    /// Replaces the value of a node, returning the old value. Passing the Root replaces the root
+    /// value. Only the value changes, so the node keeps its index, its children, and its place in
+    /// the graph.
+    ///
+    /// Note: this returns `None` if the node doesn't exist, in which case `value` is dropped.
+    pub fn replace(&mut self, node_index: NodeIndex, value: T) -> Option<T> {
+        self.value_mut(node_index).map(|slot| std::mem::replace(slot, value))
+    }
+
+    /// Replaces the value of a node, returning the old value. The node's children, and its place
+    /// in the graph, are left exactly as they were, so this is safe to use even when the new value
+    /// is a fresh start for the node. This is the same as [replace].
+    ///
+    /// Note: this returns `None` if the node doesn't exist, in which case `value` is dropped.
+    ///
+    /// [replace]: Self::replace
+    pub fn replace_preserving_children(&mut self, node_index: NodeIndex, value: T) -> Option<T> {
+        self.replace(node_index, value)
+    }
+
+    /// Shifts the values of the given nodes one step around a cycle, so the value of `indices[0]`
+    /// moves to `indices[1]`, and so on, with the value of the last node moving to `indices[0]`.
+    /// The Root may be included. Only values move, so the structure of the graph, and every
+    /// index, is left exactly as it was.
+    ///
+    /// If any index doesn't exist, or appears more than once, this returns `Err` and nothing
+    /// moves.
+    pub fn rotate_values(&mut self, indices: &[NodeIndex]) -> Result<(), SceneGraphError> {
+        let mut seen = HashSet::with_capacity(indices.len());
+        for &node_index in indices {
+            if !self.contains(node_index) {
+                return Err(SceneGraphError::NodeDoesNotExist);
             }
+            if !seen.insert(node_index) {
+                return Err(SceneGraphError::DuplicateIndex);
+            }
+        }
+
+        // swapping the first slot with each of the others in turn walks every value forward once
+        for &node_index in indices.iter().skip(1) {
+            self.swap_values(indices[0], node_index);
+        }
+
+        Ok(())
+    }
+
+    /// Gets the root node's value.
+    pub fn root(&self) -> &T {
+        &self.root
+    }
+
+    /// Gets the root node's value mutably.
+    pub fn root_mut(&mut self) -> &mut T {
+        &mut self.root
+    }
+
+    /// Marks a node as dirty, so that it will be visited by [iter_dirty]. Nodes stay dirty until
+    /// [clear_dirty] is called. Nothing in the SceneGraph marks nodes dirty on its own.
+    ///
+    /// Note: the Root is not a true node, so this always returns `Err` for it.
+    ///
+    /// [iter_dirty]: Self::iter_dirty
+    /// [clear_dirty]: Self::clear_dirty
+    pub fn mark_dirty(&mut self, node_index: NodeIndex) -> Result<(), NodeDoesNotExist> {
+        self.get_mut(node_index).ok_or(NodeDoesNotExist)?.dirty = true;
+
+        Ok(())
+    }
+
+    /// Clears the dirty flag on every node.
+    pub fn clear_dirty(&mut self) {
+        for (_, node) in self.arena.iter_mut() {
+            node.dirty = false;
+        }
+    }
+
+    /// Iterate immutably over only the dirty nodes in the Scene Graph, in a depth first
+    /// traversal. See [mark_dirty] for more information.
+    ///
+    /// [mark_dirty]: Self::mark_dirty
+    pub fn iter_dirty(&self) -> impl Iterator<Item = (NodeIndex, &T)> {
+        self.iter_detailed()
+            .filter(|visit| self.get(visit.index).unwrap().dirty)
+            .map(|visit| (visit.index, visit.value))
+    }
+
+    /// Returns the number of *direct* children of a given Node.
+    ///
+    /// This operation is O1 over the number of children. Note: this returns `None` if the node
+    /// doesn't exist.
+    pub fn child_count(&self, node_index: NodeIndex) -> Option<usize> {
+        let children = match node_index {
+            NodeIndex::Root => self.root_children,
+            NodeIndex::Branch(idx) => self.arena.get(idx)?.children,
+        };
+
+        Some(children.map_or(0, |v| v.count))
+    }
+
+    /// Returns the number of leaves in the subtree at the given Node, which is also the number of
+    /// paths from it down to a leaf. A leaf counts itself, so this is never `0`.
+    ///
+    /// This walks the whole subtree. Note: this returns `None` if the node doesn't exist.
+    pub fn leaf_descendant_count(&self, node_index: NodeIndex) -> Option<usize> {
+        if !self.contains(node_index) {
+            return None;
+        }
+
+        let leaves = SceneGraphIterDetailed::new(self, node_index)
+            .filter(|visit| !self.get(visit.index).unwrap().has_children())
+            .count();
+
+        Some(leaves.max(1))
+    }
+
+    /// Returns the first node, in a depth first traversal, whose value fulfills `predicate`. The
+    /// search stops as soon as one is found. Every node is checked, whether or not its ancestors
+    /// matched. As with [iter], the root is not included.
+    ///
+    /// [iter]: Self::iter
+    pub fn find<F: FnMut(&T) -> bool>(&self, mut predicate: F) -> Option<(NodeIndex, &T)> {
+        self.iter_detailed()
+            .find(|visit| predicate(visit.value))
+            .map(|visit| (visit.index, visit.value))
+    }
+
+    /// Returns the first node, in a depth first traversal, whose value fulfills `predicate`, with
+    /// its value mutably. This searches exactly like [find].
+    ///
+    /// [find]: Self::find
+    pub fn find_mut<F: FnMut(&T) -> bool>(&mut self, predicate: F) -> Option<(NodeIndex, &mut T)> {
+        let node_index = self.find(predicate)?.0;
+
+        self.value_mut(node_index).map(|value| (node_index, value))
+    }
+
+    /// Iterate immutably over every leaf, which is every node without children, in a depth first
+    /// traversal. As with [iter], the root is never included, even when it has no children.
+    ///
+    /// [iter]: Self::iter
+    pub fn leaves(&self) -> impl Iterator<Item = (NodeIndex, &T)> {
+        self.iter_detailed()
+            .filter(|visit| !self.get(visit.index).unwrap().has_children())
+            .map(|visit| (visit.index, visit.value))
+    }
+
+    /// Returns the deepest leaf in the graph, along with its depth, where the children of the root
+    /// have a depth of `1`. If several leaves are equally deep, the first in a depth first
+    /// traversal is returned.
+    ///
+    /// Note: this returns `None` if the graph is empty, since the root is not counted as a leaf.
+    pub fn deepest_leaf(&self) -> Option<(NodeIndex, usize)> {
+        self.leaves_with_depth().fold(None, |deepest, (node_index, depth)| match deepest {
+            Some((_, deepest_depth)) if deepest_depth >= depth => deepest,
+            _ => Some((node_index, depth)),
+        })
+    }
+
+    /// Returns the shallowest leaf in the graph, along with its depth, where the children of the
+    /// root have a depth of `1`. If several leaves are equally shallow, the first in a depth first
+    /// traversal is returned.
+    ///
+    /// Note: this returns `None` if the graph is empty, since the root is not counted as a leaf.
+    pub fn shallowest_leaf(&self) -> Option<(NodeIndex, usize)> {
+        self.leaves_with_depth().min_by_key(|&(_, depth)| depth)
+    }
+
+    /// Returns the number of edges between the root and the given node, so the Root has a depth of
+    /// `0` and its children have a depth of `1`, matching [NodeVisit::depth].
+    ///
+    /// This walks up the parent links from the node. Note: this returns `None` if the node doesn't
+    /// exist.
+    pub fn depth(&self, mut node_index: NodeIndex) -> Option<usize> {
+        let mut depth = 0;
+        while let NodeIndex::Branch(idx) = node_index {
+            node_index = self.arena.get(idx)?.parent;
+            depth += 1;
+        }
+
+        Some(depth)
+    }
+
+    /// Returns the lowest common ancestor of `a` and `b`, which is the deepest node with both of
+    /// them in its subtree. If one node is an ancestor of the other, that node is their lowest
+    /// common ancestor, and any two children of the root have the Root as theirs.
+    ///
+    /// This walks up from both nodes once. Note: this returns `None` if either node doesn't exist.
+    pub fn lowest_common_ancestor(&self, a: NodeIndex, b: NodeIndex) -> Option<NodeIndex> {
+        self.common_ancestor(a, b).map(|(ancestor, _)| ancestor)
+    }
+
+    /// Returns the path of nodes from `from` to `to`, climbing up to their
+    /// [lowest_common_ancestor] and back down. Both ends are included, and the common ancestor
+    /// appears exactly once, so the path from a node to itself is just that node.
+    ///
+    /// Note: this returns `None` if either node doesn't exist.
+    ///
+    /// [lowest_common_ancestor]: Self::lowest_common_ancestor
+    pub fn path_between(&self, from: NodeIndex, to: NodeIndex) -> Option<Vec<NodeIndex>> {
+        let ancestor = self.lowest_common_ancestor(from, to)?;
+
+        let climb = |mut node_index: NodeIndex| {
+            let mut path = vec![];
+            while node_index != ancestor {
+                path.push(node_index);
+                node_index = self.parent(node_index).unwrap();
+            }
+
+            path
+        };
+
+        let mut path = climb(from);
+        path.push(ancestor);
+        path.extend(climb(to).into_iter().rev());
+
+        Some(path)
+    }
+
+    /// Returns the depth of the lowest common ancestor of `a` and `b`, where the Root has a depth
+    /// of `0`. If one node is an ancestor of the other, that node is their lowest common
+    /// ancestor, so this is its depth.
+    ///
+    /// This walks up from both nodes once. Note: this returns `None` if either node doesn't exist.
+    pub fn lca_depth(&self, a: NodeIndex, b: NodeIndex) -> Option<usize> {
+        self.common_ancestor(a, b).map(|(_, depth)| depth)
+    }
+
+    /// Returns the nearest node at or above the lowest common ancestor of `a` and `b` whose value
+    /// matches the predicate, which is the smallest matching subtree holding both. The Root is
+    /// checked last.
+    ///
+    /// Note: this returns `None` if either node doesn't exist, or if no such ancestor matches.
+    pub fn enclosing_match(&self, a: NodeIndex, b: NodeIndex, predicate: impl Fn(&T) -> bool) -> Option<NodeIndex> {
+        let (mut node_index, _) = self.common_ancestor(a, b)?;
+
+        loop {
+            match node_index {
+                NodeIndex::Root => return predicate(&self.root).then_some(NodeIndex::Root),
+                NodeIndex::Branch(idx) => {
+                    let node = &self.arena[idx];
+                    if predicate(&node.value) {
+                        return Some(node_index);
+                    }
+
+                    node_index = node.parent;
+                }
+            }
+        }
+    }
+
+    /// Returns the parent NodeIndex of a given Node.
+    ///
+    /// This operation is O1 over the number of nodes in the SceneGraph.
+    /// Note: this returns `None` for the Root, and for an index which isn't in the graph.
+    pub fn parent(&self, node_index: NodeIndex) -> Option<NodeIndex> {
+        self.get(node_index).map(|v| v.parent)
+    }
+
+    /// Iterate mutably over the Scene Graph in a depth first traversal.
+    pub fn iter_mut(&mut self) -> SceneGraphIterMut<'_, T> {
+        SceneGraphIterMut::new(self, NodeIndex::Root)
+    }
+
+    /// Iterate mutably over the Scene Graph in a depth first traversal, yielding each node's path
+    /// alongside its value. A path is the position of each node among its siblings, from the
+    /// children of the root down to the node itself, so the first child of the root is `[0]`, and
+    /// its second child is `[0, 1]`.
+    pub fn iter_mut_with_path(&mut self) -> impl Iterator<Item = (Vec<usize>, &mut T)> {
+        let mut path = Vec::new();
+        let paths = Vec::from_iter(self.iter_detailed().map(|visit| {
+            path.truncate(visit.depth - 1);
+            path.push(visit.sibling_index);
+
+            path.clone()
+        }));
+
+        paths.into_iter().zip(self.iter_mut().map(|(_parent, value)| value))
+    }
+
+    /// Iterate mutably over the Scene Graph in a depth first traversal, skipping branches/subtrees where
+    /// a child does not fulfill the predicate.
+    pub fn iter_mut_predicate<F: FnMut(&T) -> bool>(&mut self, predicate: F) -> SceneGraphIterMutPredicate<'_, T, F> {
+        SceneGraphIterMutPredicate::new(self, NodeIndex::Root, predicate)
+    }
+
+    /// Iterate mutably over the descendants of `node_index` like [iter_mut_predicate], skipping
+    /// branches/subtrees where a child does not fulfill the predicate. The given node gates the
+    /// traversal the way the root does for a whole graph: if it doesn't fulfill the predicate,
+    /// nothing is visited. Nodes outside of the subtree are never visited.
+    ///
+    /// [iter_mut_predicate]: Self::iter_mut_predicate
+    pub fn iter_mut_predicate_from<F: FnMut(&T) -> bool>(
+        &mut self,
+        node_index: NodeIndex,
+        predicate: F,
+    ) -> Result<SceneGraphIterMutPredicate<'_, T, F>, NodeDoesNotExist> {
+        if !self.contains(node_index) {
+            return Err(NodeDoesNotExist);
+        }
+
+        Ok(SceneGraphIterMutPredicate::new(self, node_index, predicate))
+    }
+
+    /// Iterate mutably over the Scene Graph exactly like [iter_mut_predicate], descending only into
+    /// nodes which fulfill `descend`, but ending the traversal entirely once a yielded node
+    /// fulfills `stop`. That node is still yielded, and every call to `next` afterwards returns
+    /// `None`.
+    ///
+    /// [iter_mut_predicate]: Self::iter_mut_predicate
+    pub fn iter_mut_predicate_with_stop<F: FnMut(&T) -> bool, S: FnMut(&T) -> bool>(
+        &mut self,
+        descend: F,
+        stop: S,
+    ) -> SceneGraphIterMutPredicateWithStop<'_, T, F, S> {
+        SceneGraphIterMutPredicateWithStop::new(self.iter_mut_predicate(descend), stop)
+    }
+
+    /// Iterate mutably over the Scene Graph in a depth first, post-order traversal, so every node
+    /// is visited after all of its descendants. This is useful for bottom-up work, like computing
+    /// a value from values already computed for the children.
+    ///
+    /// Note: like the other iterators, the root is not visited.
+    pub fn iter_post_order_mut(&mut self) -> SceneGraphIterPostOrderMut<'_, T> {
+        SceneGraphIterPostOrderMut::new(self, NodeIndex::Root)
+    }
+
+    /// Iterate immutably over the Scene Graph in a depth first traversal.
+    pub fn iter(&self) -> SceneGraphIter<'_, T> {
+        self.iter_from_node(NodeIndex::Root).unwrap()
+    }
+
+    /// Iterate immutably over the values of the Scene Graph in a depth first traversal. This is
+    /// the same order as [iter], without the parents. As with [iter], the root is not included.
+    ///
+    /// [iter]: Self::iter
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_parent, value)| value)
+    }
+
+    /// Iterate immutably over the Scene Graph in a depth first, post-order traversal, so every node
+    /// is visited after all of its descendants. This is useful for bottom-up work, like freeing
+    /// resources or computing bounds from the leaves upward.
+    ///
+    /// Note: like the other iterators, the root is not visited.
+    pub fn iter_post_order(&self) -> SceneGraphIterPostOrder<'_, T> {
+        SceneGraphIterPostOrder::new(self, NodeIndex::Root)
+    }
+
+    /// Iterate immutably over the Scene Graph in a depth first traversal, skipping branches/subtrees
+    /// where a child does not fulfill the predicate. If the root does not fulfill the predicate,
+    /// nothing is visited at all.
+    pub fn iter_predicate<F: FnMut(&T) -> bool>(&self, predicate: F) -> SceneGraphIterPredicate<'_, T, F> {
+        SceneGraphIterPredicate::new(self, NodeIndex::Root, predicate)
+    }
+
+    /// Iterate immutably over the Scene Graph in a depth first traversal, yielding a [NodeVisit]
+    /// for each node. Each visit carries the node's index, value, depth, position among its
+    /// siblings, and parent, which saves querying each of those separately.
+    ///
+    /// Once [preorder_indices] has been cached, `nth` on this iterator jumps straight to the node
+    /// rather than walking past every node before it.
+    ///
+    /// [preorder_indices]: Self::preorder_indices
+    pub fn iter_detailed(&self) -> SceneGraphIterDetailed<'_, T> {
+        SceneGraphIterDetailed::new(self, NodeIndex::Root)
+    }
+
+    /// Iterate immutably over the Scene Graph in a depth first traversal, yielding each node's
+    /// level alongside its index and value. This is the indentation level for a tree view, so the
+    /// children of the root are at level `0`, one less than their [NodeVisit::depth].
+    pub fn iter_with_depth(&self) -> impl Iterator<Item = (usize, NodeIndex, &T)> {
+        self.iter_detailed()
+            .map(|visit| (visit.depth - 1, visit.index, visit.value))
+    }
+
+    /// Iterate immutably over the Scene Graph in a depth first traversal, yielding each node's
+    /// index, its parent's index, and its value. This is handy for building a flat table with
+    /// parent references.
+    ///
+    /// The children of the root report a parent of `Some(NodeIndex::Root)`. Since the root itself
+    /// isn't visited, the parent is never `None`.
+    pub fn iter_with_parent_index(&self) -> impl Iterator<Item = (NodeIndex, Option<NodeIndex>, &T)> {
+        self.iter_detailed()
+            .map(|visit| (visit.index, Some(visit.parent), visit.value))
+    }
+
+    /// Iterate immutably over the Scene Graph in a breadth first traversal, so every node at one
+    /// depth is visited before any node at the next. Nodes at the same depth are visited in
+    /// sibling order, with the children of earlier parents first.
+    pub fn iter_bfs(&self) -> SceneGraphIterBfs<'_, T> {
+        SceneGraphIterBfs::new(self, NodeIndex::Root)
+    }
+
+    /// Iterate immutably over the descendants of `node_index` in a breadth first traversal, like
+    /// [iter_bfs]. The node itself is not visited, so this starts with its children.
+    ///
+    /// [iter_bfs]: Self::iter_bfs
+    pub fn iter_bfs_from(&self, node_index: NodeIndex) -> Result<SceneGraphIterBfs<'_, T>, NodeDoesNotExist> {
+        if !self.contains(node_index) {
+            return Err(NodeDoesNotExist);
+        }
+
+        Ok(SceneGraphIterBfs::new(self, node_index))
+    }
+
+    /// Iterate immutably over the Scene Graph in a depth first traversal, where siblings are visited
+    /// in *reverse* order. Parents are still visited before their children, so this is not a
+    /// post-order traversal. This is useful for hit-testing, where the last drawn node should be
+    /// tested first.
+    pub fn iter_rev(&self) -> SceneGraphIterRev<'_, T> {
+        SceneGraphIterRev::new(self)
+    }
+
+    /// Iterate immutably over the Scene Graph in a depth first traversal, without descending into
+    /// the nodes in `skip`. Those nodes are still yielded themselves, but none of their descendants
+    /// are. This is useful for something like collapsed nodes in a tree view.
+    pub fn iter_skipping<'a>(&'a self, skip: &'a HashSet<NodeIndex>) -> SceneGraphIterSkipping<'a, T> {
+        SceneGraphIterSkipping::new(self, skip)
+    }
+
+    /// Returns every node's index in a depth first traversal, in the same order as [iter]. The
+    /// list is computed on first use and then cached, so repeated calls on an unchanged graph
+    /// don't traverse anything. Any change to the structure of the graph clears the cache, but
+    /// changing values does not.
+    ///
+    /// Note: like the other traversals, the root is not included.
+    ///
+    /// [iter]: Self::iter
+    pub fn preorder_indices(&self) -> &[NodeIndex] {
+        self.preorder_cache
+            .get_or_init(|| self.iter_detailed().map(|visit| visit.index).collect())
+    }
+
+    /// Returns every node's index in *reverse* depth first order, so that every child comes before
+    /// its parent. This is cheaper than a post-order traversal, and is enough for something like
+    /// freeing resources bottom-up. It uses the same cache as [preorder_indices].
+    ///
+    /// [preorder_indices]: Self::preorder_indices
+    pub fn reverse_preorder_indices(&self) -> Vec<NodeIndex> {
+        self.preorder_indices().iter().rev().copied().collect()
+    }
+
+    /// Iterate immutably over the Scene Graph out of order. This is useful for speed.
+    pub fn iter_out_of_order(&self) -> impl Iterator<Item = (NodeIndex, &T)> {
+        self.arena.iter().map(|(k, v)| (NodeIndex::Branch(k), &v.value))
+    }
+
+    /// Iterate mutably over the Scene Graph out of order. This is useful for speed.
+    pub fn iter_out_of_order_mut(&mut self) -> impl Iterator<Item = (NodeIndex, &mut T)> {
+        self.arena.iter_mut().map(|(k, v)| (NodeIndex::Branch(k), &mut v.value))
+    }
+
+    /// Iterate immutably over the descendants of `node_index` in a depth first traversal.
+    ///
+    /// A stale or otherwise invalid `Branch` index gives a [NodeDoesNotExist] error, while a node
+    /// which simply has no children gives an empty iterator.
+    pub fn iter_from_node(&self, node_index: NodeIndex) -> Result<SceneGraphIter<'_, T>, NodeDoesNotExist> {
+        let (parent_value, children) = match node_index {
+            NodeIndex::Root => (&self.root, self.root_children.as_ref()),
+            NodeIndex::Branch(idx) => {
+                let node = self.arena.get(idx).ok_or(NodeDoesNotExist)?;
+
+                (&node.value, node.children.as_ref())
+            }
+        };
+
+        Ok(SceneGraphIter::new(self, parent_value, children))
+    }
+
+    /// Iterate immutably over the subtree at `node_index` in a depth first traversal, yielding
+    /// `node_index` itself first, followed by its descendants. Unlike the other traversals, this
+    /// means that the root *is* yielded when starting at the Root.
+    pub fn iter_subtree_inclusive(
+        &self,
+        node_index: NodeIndex,
+    ) -> Result<impl Iterator<Item = (NodeIndex, &T)>, NodeDoesNotExist> {
+        let value = match node_index {
+            NodeIndex::Root => &self.root,
+            NodeIndex::Branch(idx) => &self.arena.get(idx).ok_or(NodeDoesNotExist)?.value,
+        };
+
+        let descendants = SceneGraphIterDetailed::new(self, node_index).map(|visit| (visit.index, visit.value));
+
+        Ok(std::iter::once((node_index, value)).chain(descendants))
+    }
+
+    /// Iterate mutably over the Scene Graph in a depth first traversal.
+    pub fn iter_mut_from_node(&mut self, node_index: NodeIndex) -> Result<SceneGraphIterMut<'_, T>, NodeDoesNotExist> {
+        match node_index {
+            NodeIndex::Root => {}
+            NodeIndex::Branch(idx) => {
+                if !self.arena.contains(idx) {
+                    return Err(NodeDoesNotExist);
+                }
+            }
+        };
+
+        Ok(SceneGraphIterMut::new(self, node_index))
+    }
+
+    /// Iterate while detaching over the Scene Graph in a depth first traversal.
+    ///
+    /// Note: the `root` will never be detached.
+    pub fn iter_detach_from_root(&mut self) -> SceneGraphDetachIter<'_, T> {
+        self.preorder_cache.take();
+        SceneGraphDetachIter::new(&mut self.arena, NodeIndex::Root, self.root_children.take())
+    }
+
+    /// Iterate while detaching over the Scene Graph in a depth first traversal.
+    /// This leaves the `node_index` given in the graph, but removes all its descendents.
+    pub fn iter_detach(&mut self, node_index: NodeIndex) -> Result<SceneGraphDetachIter<'_, T>, NodeDoesNotExist> {
+        self.preorder_cache.take();
+        let children = match node_index {
+            NodeIndex::Root => self.root_children.take(),
+            NodeIndex::Branch(br) => match self.arena.get_mut(br) {
+                Some(v) => v.children.take(),
+                None => return Err(NodeDoesNotExist),
+            },
+        };
+
+        Ok(SceneGraphDetachIter::new(&mut self.arena, node_index, children))
+    }
+
+    /// Iterate directly over only the *direct* children of `parent_index`.
+    ///
+    /// For example, given a graph:
+    /// ROOT:
+    ///     A
+    ///         B
+    ///         C
+    ///             D
+    /// using [iter_direct_children] and passing in the `parent_index` for `A` will only yield `B`
+    /// and `C`, *not* `D`. For that kind of depth first traversal, using `iter_on_node`.
+    ///
+    /// [iter_direct_children]: [Self::iter_direct_children]
+    pub fn iter_direct_children(
+        &self,
+        parent_index: NodeIndex,
+    ) -> Result<SceneGraphChildIter<'_, T>, NodeDoesNotExist> {
+        if let NodeIndex::Branch(idx) = parent_index {
+            self.arena.get(idx).ok_or(NodeDoesNotExist)?;
+        }
+
+        Ok(SceneGraphChildIter::new(self, parent_index))
+    }
+
+    /// Iterates over the *direct* children of `parent_index`, yielding each child's index alongside
+    /// its value. Unlike [iter_direct_children], this never fails: a leaf, or an index which isn't
+    /// in the graph, simply yields nothing.
+    ///
+    /// [iter_direct_children]: Self::iter_direct_children
+    pub fn children(&self, parent_index: NodeIndex) -> impl Iterator<Item = (NodeIndex, &T)> {
+        let children = match parent_index {
+            NodeIndex::Root => self.root_children,
+            NodeIndex::Branch(idx) => self.arena.get(idx).and_then(|v| v.children),
+        };
+
+        self.child_indices(children)
+            .map(|idx| (NodeIndex::Branch(idx), &self.arena[idx].value))
+    }
+
+    /// Iterates over the *direct* children of `parent_index` in reverse, from the last child to the
+    /// first, by following each child's link to its previous sibling. As with [children], a leaf,
+    /// or an index which isn't in the graph, yields nothing.
+    ///
+    /// [children]: Self::children
+    pub fn children_rev(&self, parent_index: NodeIndex) -> impl Iterator<Item = (NodeIndex, &T)> {
+        let children = match parent_index {
+            NodeIndex::Root => self.root_children,
+            NodeIndex::Branch(idx) => self.arena.get(idx).and_then(|v| v.children),
+        };
+
+        std::iter::successors(children.map(|v| v.last), move |&idx| self.arena[idx].last_sibling)
+            .map(|idx| (NodeIndex::Branch(idx), &self.arena[idx].value))
+    }
+
+    /// Iterates mutably over the *direct* children of `parent_index`, yielding each child's index
+    /// alongside its value. A leaf, or an index which isn't in the graph, yields nothing.
+    pub fn children_mut(&mut self, parent_index: NodeIndex) -> SceneGraphChildIterMut<'_, T> {
+        SceneGraphChildIterMut::new(self, parent_index)
+    }
+
+    /// Iterates over every run of `size` consecutive *direct* children of `parent_index`, like
+    /// [slice::windows]. Each window holds the children's values in sibling order, and each window
+    /// starts one sibling after the last. A parent with fewer than `size` children yields nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn children_windows(
+        &self,
+        parent_index: NodeIndex,
+        size: usize,
+    ) -> Result<impl Iterator<Item = Vec<&T>>, NodeDoesNotExist> {
+        assert!(size != 0, "window size must be non-zero");
+
+        let children = Vec::from_iter(self.iter_direct_children(parent_index)?);
+        let window_count = (children.len() + 1).saturating_sub(size);
+
+        Ok((0..window_count).map(move |start| children[start..start + size].to_vec()))
+    }
+
+    /// Iterates over the ancestors of `node_index`, starting with its parent and ending with the
+    /// Root. This walks up the parent links lazily, without allocating.
+    ///
+    /// Note: this yields nothing for the Root, and for an index which isn't in the graph.
+    pub fn ancestors(&self, node_index: NodeIndex) -> SceneGraphAncestors<'_, T> {
+        SceneGraphAncestors::new(self, node_index)
+    }
+
+    /// Applies a function on the given node and all its direct ancestors.
+    pub fn for_each_upward(&mut self, starting_node: NodeIndex, function: fn(&mut T)) {
+        let mut current_node = Some(starting_node);
+        while let Some(node) = current_node {
+            if let NodeIndex::Branch(idx) = node {
+                current_node = self.arena.get_mut(idx)
+                    .map(|n| {
+                        function(&mut n.value);
+                        n.parent
+                    });
+            } else {
+                function(&mut self.root);
+                current_node = None;
+            }
+        }
+    }
+
+    /// Encodes the graph as a binary tree in the classic left-child right-sibling form. Every node
+    /// becomes a node with at most two children: first its "left" child, which encodes its own
+    /// first child, and then its "right" child, which encodes its next sibling.
+    ///
+    /// A node with a next sibling but no children of its own is given a `None` placeholder as its
+    /// left child, so that the right child is always second. Every real value is wrapped in
+    /// `Some`. The root has no siblings, so it has at most a left child. This can be decoded with
+    /// [from_lcrs].
+    ///
+    /// [from_lcrs]: Self::from_lcrs
+    pub fn to_lcrs(&self) -> SceneGraph<Option<T>>
+    where
+        T: Clone,
+    {
+        let mut lcrs = SceneGraph::with_capacity(Some(self.root.clone()), self.len());
+
+        let mut stacks = Vec::new();
+        if let Some(children) = self.root_children {
+            stacks.push((children.first, NodeIndex::Root));
+        }
+
+        while let Some((idx, lcrs_parent)) = stacks.pop() {
+            let node = &self.arena[idx];
+            let lcrs_idx = lcrs.attach(lcrs_parent, Some(node.value.clone())).unwrap();
+
+            if let Some(next_sibling) = node.next_sibling {
+                if node.children.is_none() {
+                    lcrs.attach(lcrs_idx, None).unwrap();
+                }
+
+                stacks.push((next_sibling, lcrs_idx));
+            }
+
+            // our first child is popped next, so it's always attached before our next sibling
+            if let Some(children) = node.children {
+                stacks.push((children.first, lcrs_idx));
+            }
+        }
+
+        lcrs
+    }
+
+    /// Decodes a graph from the left-child right-sibling form produced by [to_lcrs].
+    ///
+    /// This returns `None` if the graph isn't in that form: if the root or a right child is a
+    /// placeholder, if a placeholder has children, or if any node has more than two children (or
+    /// the root more than one).
+    ///
+    /// [to_lcrs]: Self::to_lcrs
+    pub fn from_lcrs(mut lcrs: SceneGraph<Option<T>>) -> Option<Self> {
+        let mut sg = SceneGraph::with_capacity(lcrs.root.take()?, lcrs.len());
+
+        let mut stacks = Vec::new();
+        match lcrs.root_children {
+            None => {}
+            Some(children) if children.count == 1 => stacks.push((children.first, NodeIndex::Root)),
+            Some(_) => return None,
+        }
+
+        while let Some((lcrs_idx, parent)) = stacks.pop() {
+            let lcrs_node = &mut lcrs.arena[lcrs_idx];
+            let new_idx = sg.attach(parent, lcrs_node.value.take()?).unwrap();
+
+            let (left, right) = match lcrs_node.children {
+                None => (None, None),
+                Some(children) if children.count == 1 => (Some(children.first), None),
+                Some(children) if children.count == 2 => (Some(children.first), Some(children.last)),
+                Some(_) => return None,
+            };
+
+            if let Some(right) = right {
+                stacks.push((right, parent));
+            }
+
+            if let Some(left) = left {
+                let left_node = &lcrs.arena[left];
+                if left_node.value.is_some() {
+                    stacks.push((left, new_idx));
+                } else if left_node.has_children() {
+                    return None;
+                }
+            }
+        }
+
+        Some(sg)
+    }
+
+    /// Returns the number of distinct values in the graph, including the root.
+    pub fn distinct_value_count(&self) -> usize
+    where
+        T: Eq + Hash,
+    {
+        let mut values = HashSet::with_capacity(self.len() + 1);
+        values.insert(&self.root);
+        values.extend(self.arena.iter().map(|(_, node)| &node.value));
+
+        values.len()
+    }
+
+    /// Returns the topmost node of every subtree in which *every* node matches the predicate.
+    /// Nodes within those subtrees are not returned themselves, so every returned node's parent
+    /// has at least one non-matching descendant (or fails the predicate itself).
+    ///
+    /// If the entire graph matches, including the root, this returns only `NodeIndex::Root`.
+    /// Otherwise, the nodes are returned in depth first order.
+    pub fn maximal_matching_subtrees(&self, mut predicate: impl FnMut(&T) -> bool) -> Vec<NodeIndex> {
+        let mut matching = HashSet::new();
+        let mut disqualified = HashSet::new();
+
+        let visits = Vec::from_iter(self.iter_detailed());
+
+        // children come before their parents when walking backwards, so we know if any
+        // descendant failed by the time we reach a node.
+        for visit in visits.iter().rev() {
+            if !disqualified.contains(&visit.index) && predicate(visit.value) {
+                matching.insert(visit.index);
+            } else {
+                disqualified.insert(visit.parent);
+            }
+        }
+
+        if !disqualified.contains(&NodeIndex::Root) && predicate(&self.root) {
+            return vec![NodeIndex::Root];
+        }
+
+        self.topmost_matching(|idx, _| matching.contains(&idx))
+            .into_iter()
+            .map(NodeIndex::Branch)
+            .collect()
+    }
+
+    /// Returns `true` if, for every node (including the root), the heights of its children's
+    /// subtrees differ by at most `tolerance`. Nodes with zero or one child are always balanced.
+    pub fn is_height_balanced(&self, tolerance: usize) -> bool {
+        // the (shortest, tallest) heights among each node's children
+        let mut child_heights: HashMap<NodeIndex, (usize, usize)> = HashMap::new();
+
+        let visits = Vec::from_iter(self.iter_detailed());
+
+        // walking backwards means we always see children before their parents
+        for visit in visits.iter().rev() {
+            let height = match child_heights.remove(&visit.index) {
+                Some((shortest, tallest)) => {
+                    if tallest - shortest > tolerance {
+                        return false;
+                    }
+
+                    tallest + 1
+                }
+                None => 0,
+            };
+
+            let parent_heights = child_heights.entry(visit.parent).or_insert((height, height));
+            parent_heights.0 = parent_heights.0.min(height);
+            parent_heights.1 = parent_heights.1.max(height);
+        }
+
+        match child_heights.get(&NodeIndex::Root) {
+            Some((shortest, tallest)) => tallest - shortest <= tolerance,
+            None => true,
+        }
+    }
+
+    /// Copies the graph into two contiguous, parallel `Vec`s laid out in a depth first (pre-order)
+    /// traversal. The first `Vec` holds the values, and the second holds the position of each
+    /// value's parent within the first `Vec`.
+    ///
+    /// Unlike the other traversals, the root *is* included, always at position `0`, and it is the
+    /// only entry with a parent of `None`. Because the layout is pre-order, a parent always comes
+    /// before any of its children.
+    pub fn to_contiguous(&self) -> (Vec<T>, Vec<Option<usize>>)
+    where
+        T: Clone,
+    {
+        let mut values = Vec::with_capacity(self.len() + 1);
+        let mut parent_indices = Vec::with_capacity(self.len() + 1);
+
+        values.push(self.root.clone());
+        parent_indices.push(None);
+
+        let mut stacks = Vec::new();
+        if let Some(children) = self.root_children {
+            stacks.push((0, children.first));
+        }
+
+        while let Some((parent_position, idx)) = stacks.pop() {
+            let node = &self.arena[idx];
+            let position = values.len();
+
+            values.push(node.value.clone());
+            parent_indices.push(Some(parent_position));
+
+            // siblings go on first, so that children are handled before them
+            if let Some(next_sibling) = node.next_sibling {
+                stacks.push((parent_position, next_sibling));
+            }
+
+            if let Some(children) = node.children {
+                stacks.push((position, children.first));
+            }
+        }
+
+        (values, parent_indices)
+    }
+
+    /// Computes a value for every node from the bottom up, including the root. The function is
+    /// given a node's value, along with the values already computed for its direct children, in
+    /// sibling order. Leaves are given an empty slice.
+    ///
+    /// This is useful for computing something like the bounds of every subtree in one pass.
+    pub fn reduce_indexed<R>(&self, mut function: impl FnMut(&T, &[R]) -> R) -> HashMap<NodeIndex, R> {
+        let mut reductions: HashMap<NodeIndex, R> = HashMap::with_capacity(self.len() + 1);
+        let mut child_reductions = Vec::new();
+
+        let mut reduce = |node_index: NodeIndex, value: &T, children: Option<Children>| {
+            // the children are already done, so we borrow their results for a moment...
+            let child_indices = Vec::from_iter(self.child_indices(children).map(NodeIndex::Branch));
+            child_reductions.extend(child_indices.iter().map(|idx| reductions.remove(idx).unwrap()));
+
+            let reduction = function(value, &child_reductions);
+
+            // ...and then put them right back
+            for (idx, child_reduction) in child_indices.into_iter().zip(child_reductions.drain(..)) {
+                reductions.insert(idx, child_reduction);
+            }
+            reductions.insert(node_index, reduction);
+        };
+
+        let visits = Vec::from_iter(self.iter_detailed());
+        for visit in visits.iter().rev() {
+            let children = self.get(visit.index).unwrap().children;
+            reduce(visit.index, visit.value, children);
+        }
+        reduce(NodeIndex::Root, &self.root, self.root_children);
+
+        reductions
+    }
+
+    /// Numbers every node, including the root, with the times it is entered and exited during a
+    /// depth first traversal. A node `a` is an ancestor of `b` exactly when
+    /// `in_time(a) < in_time(b) && out_time(b) < out_time(a)`, so after this one pass, ancestry
+    /// checks are O1.
+    ///
+    /// Every time is distinct, and the root is always entered at `0`.
+    pub fn euler_tour(&self) -> HashMap<NodeIndex, (usize, usize)> {
+        let mut times = HashMap::with_capacity(self.len() + 1);
+        let mut time = 0;
+
+        // the nodes we've entered but not yet exited, along with their depths
+        let mut open = vec![(NodeIndex::Root, 0, time)];
+
+        let mut close_until = |open: &mut Vec<(NodeIndex, usize, usize)>, depth: usize, time: &mut usize| {
+            while let Some(&(node_index, node_depth, in_time)) = open.last() {
+                if node_depth < depth {
+                    break;
+                }
+
+                open.pop();
+                *time += 1;
+                times.insert(node_index, (in_time, *time));
+            }
+        };
+
+        for visit in self.iter_detailed() {
+            // anything at least as deep as us is finished
+            close_until(&mut open, visit.depth, &mut time);
+
+            time += 1;
+            open.push((visit.index, visit.depth, time));
+        }
+        close_until(&mut open, 0, &mut time);
+
+        times
+    }
+
+    /// Returns the number of nodes in every node's subtree, counting the node itself, in one
+    /// bottom-up pass. The root is included, and its subtree holds every node, so its size is one
+    /// more than [len].
+    ///
+    /// [len]: Self::len
+    pub fn subtree_sizes(&self) -> HashMap<NodeIndex, usize> {
+        self.reduce_indexed(|_, child_sizes| 1 + child_sizes.iter().sum::<usize>())
+    }
+
+    /// Counts how many times each value occurs in the graph, including the root value. This is
+    /// handy for deciding whether repeated values are worth sharing behind an `Rc`.
+    pub fn value_frequencies(&self) -> HashMap<&T, usize>
+    where
+        T: Eq + Hash,
+    {
+        let mut frequencies = HashMap::new();
+        *frequencies.entry(&self.root).or_insert(0) += 1;
+        for (_, value) in self.iter() {
+            *frequencies.entry(value).or_insert(0) += 1;
+        }
+
+        frequencies
+    }
+
+    /// Computes a Merkle hash for every node, including the root, using a fresh `H` for each. A
+    /// node's hash covers its own value and the hashes of its children, in order, so two subtrees
+    /// hash the same exactly when they have (barring collisions) the same values in the same shape.
+    ///
+    /// Choosing `H` lets these hashes line up with an external store, or differ between runs.
+    pub fn subtree_hashes<H: std::hash::Hasher + Default>(&self) -> HashMap<NodeIndex, u64>
+    where
+        T: Hash,
+    {
+        self.reduce_indexed(|value, child_hashes| {
+            let mut hasher = H::default();
+            value.hash(&mut hasher);
+            child_hashes.hash(&mut hasher);
+
+            hasher.finish()
+        })
+    }
+
+    /// Finds every group of two or more identical subtrees, where identical means the same values
+    /// in the same shape. For each group, this returns one canonical copy of the shared subtree,
+    /// with the group's first node as its root, alongside the indices of every node heading a copy,
+    /// in depth first order. The graph itself is left as is.
+    ///
+    /// Groups are in the depth first order of their first node. Nested duplicates are reported
+    /// too, so two identical subtrees with identical children give a group for each shape. The
+    /// root is never part of a group.
+    pub fn extract_shared(&self) -> (Vec<SceneGraph<T>>, Vec<Vec<NodeIndex>>)
+    where
+        T: Hash + Eq + Clone,
+    {
+        let hashes = self.subtree_hashes::<std::collections::hash_map::DefaultHasher>();
+
+        // bucket by hash first, then split each bucket by real equality in case of collisions
+        let mut groups: Vec<Vec<NodeIndex>> = vec![];
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+        for visit in self.iter_detailed() {
+            let bucket = buckets.entry(hashes[&visit.index]).or_default();
+            let existing = bucket
+                .iter()
+                .copied()
+                .find(|&group| self.subtrees_equal(groups[group][0], visit.index));
+
+            match existing {
+                Some(group) => groups[group].push(visit.index),
+                None => {
+                    bucket.push(groups.len());
+                    groups.push(vec![visit.index]);
+                }
+            }
+        }
+
+        groups.retain(|group| group.len() > 1);
+        let canonical = Vec::from_iter(groups.iter().map(|group| self.clone_subtree(group[0]).unwrap()));
+
+        (canonical, groups)
+    }
+
+    /// Returns every node's index in a depth first traversal, along with a parallel array holding
+    /// the position of each node's parent within the first `Vec`. The children of the root have
+    /// a parent of `-1`, since, as in the other traversals, the root itself is not included.
+    ///
+    /// This compact representation is intended for uploading to a GPU.
+    pub fn parent_indices(&self) -> (Vec<NodeIndex>, Vec<i32>) {
+        let mut positions = HashMap::with_capacity(self.len());
+        let mut indices = Vec::with_capacity(self.len());
+        let mut parents = Vec::with_capacity(self.len());
+
+        for visit in self.iter_detailed() {
+            let parent = match visit.parent {
+                NodeIndex::Root => -1,
+                NodeIndex::Branch(_) => positions[&visit.parent],
+            };
+
+            positions.insert(visit.index, indices.len() as i32);
+            indices.push(visit.index);
+            parents.push(parent);
+        }
+
+        (indices, parents)
+    }
+
+    /// Builds a new graph with exactly the same shape as this one, where every value, including
+    /// the root, is given by `function`. The root is mapped first, followed by every other node in
+    /// a depth first traversal.
+    ///
+    /// The new graph has its own arena, so indices from this graph are not valid on it.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, mut function: F) -> SceneGraph<U> {
+        let mut sg = SceneGraph::with_capacity(function(&self.root), self.len());
+        let mut new_indices = HashMap::with_capacity(self.len() + 1);
+        new_indices.insert(NodeIndex::Root, NodeIndex::Root);
+
+        for visit in self.iter_detailed() {
+            let new_idx = sg.attach(new_indices[&visit.parent], function(visit.value)).unwrap();
+            new_indices.insert(visit.index, new_idx);
+        }
+
+        sg
+    }
+
+    /// Applies a function on the given node and all its descendants, without changing the
+    /// structure of the graph. The given node is visited first, followed by its descendants in a
+    /// depth first traversal.
+    pub fn map_subtree_in_place(
+        &mut self,
+        node_index: NodeIndex,
+        mut function: impl FnMut(&mut T),
+    ) -> Result<(), NodeDoesNotExist> {
+        match node_index {
+            NodeIndex::Root => function(&mut self.root),
+            NodeIndex::Branch(idx) => function(&mut self.arena.get_mut(idx).ok_or(NodeDoesNotExist)?.value),
+        }
+
+        for (_parent, value) in SceneGraphIterMut::new(self, node_index) {
+            function(value);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the whole graph, starting with the root, as indented tree art, with one line per
+    /// node. The text for each node is given by `label`. For example:
+    ///
+    /// ```text
+    /// Root
+    /// ├─ A
+    /// │  ├─ A1
+    /// │  └─ A2
+    /// └─ B
+    /// ```
+    ///
+    /// This writes straight into `w`, so nothing needs to be collected into a `String` first.
+    pub fn write_tree<W: std::fmt::Write>(&self, w: &mut W, label: impl Fn(&T) -> String) -> std::fmt::Result {
+        self.write_subtree(w, NodeIndex::Root, label)
+    }
+
+    /// Renders the whole graph as tree art, just like [write_tree], using each value's `Display`
+    /// impl as its label.
+    ///
+    /// [write_tree]: Self::write_tree
+    pub fn fmt_tree(&self) -> String
+    where
+        T: std::fmt::Display,
+    {
+        let mut output = String::new();
+        self.write_tree(&mut output, |value| value.to_string())
+            .expect("writing to a String cannot fail");
+
+        output
+    }
+
+    /// Renders the whole graph, starting with the root, as an s-expression, like
+    /// `(Root (A A1 A2) B)`. A node with children is written as a parenthesized list of its own
+    /// atom followed by its children, and a leaf is written as a bare atom.
+    ///
+    /// The text for each node is given by `atom`. Atoms which are empty, or which contain
+    /// whitespace, parentheses, or quotes, are written in double quotes, with any quotes and
+    /// backslashes inside escaped.
+    pub fn to_sexpr(&self, atom: impl Fn(&T) -> String) -> String {
+        fn push_atom(output: &mut String, atom: String) {
+            let needs_quotes = atom.is_empty()
+                || atom.chars().any(|c| c.is_whitespace() || matches!(c, '(' | ')' | '"' | '\\'));
+
+            if needs_quotes {
+                output.push('"');
+                for c in atom.chars() {
+                    if matches!(c, '"' | '\\') {
+                        output.push('\\');
+                    }
+                    output.push(c);
+                }
+                output.push('"');
+            } else {
+                output.push_str(&atom);
+            }
+        }
+
+        let mut output = String::new();
+
+        // the number of lists we're currently inside of, which is one more than the depth of the
+        // deepest open list
+        let mut open = 0;
+        if self.root_children.is_some() {
+            output.push('(');
+            open += 1;
+        }
+        push_atom(&mut output, atom(&self.root));
+
+        for visit in self.iter_detailed() {
+            while open > visit.depth {
+                output.push(')');
+                open -= 1;
+            }
+
+            output.push(' ');
+            if self.get(visit.index).unwrap().has_children() {
+                output.push('(');
+                open += 1;
+            }
+            push_atom(&mut output, atom(visit.value));
+        }
+
+        output.extend(std::iter::repeat_n(')', open));
+
+        output
+    }
+
+    /// Renders only the subtree at `node_index` as indented tree art, like [write_tree], labeling
+    /// each node with its `Debug` output. The given node is on the first line, unindented.
+    ///
+    /// [write_tree]: Self::write_tree
+    pub fn debug_subtree(&self, node_index: NodeIndex) -> Result<String, NodeDoesNotExist>
+    where
+        T: std::fmt::Debug,
+    {
+        if !self.contains(node_index) {
+            return Err(NodeDoesNotExist);
+        }
+
+        let mut output = String::new();
+        self.write_subtree(&mut output, node_index, |value| format!("{:?}", value))
+            .expect("writing to a String cannot fail");
+
+        Ok(output)
+    }
+
+    /// Follows every child and sibling link in the graph, looking for a loop. If one is found, the
+    /// nodes making up the loop are returned, in the order the links visit them.
+    ///
+    /// A graph built with this crate's methods never has a loop, so this is only a diagnostic for
+    /// graphs assembled by hand, such as with [from_parts]. Links to nodes missing from the arena
+    /// are ignored.
+    ///
+    /// [from_parts]: Self::from_parts
+    pub fn detect_cycle(&self) -> Option<Vec<NodeIndex>> {
+        let mut finished = HashSet::with_capacity(self.len());
+        let mut on_path = HashSet::new();
+
+        // the nodes we're currently beneath, along with how many of their links we've followed
+        let mut path: Vec<(Index, usize)> = Vec::new();
+        if let Some(children) = self.root_children {
+            path.push((children.first, 0));
+            on_path.insert(children.first);
+        }
+
+        while let Some((idx, followed)) = path.last_mut() {
+            let idx = *idx;
+            let link = self.arena.get(idx).and_then(|node| match *followed {
+                0 => Some(node.children.map(|v| v.first)),
+                1 => Some(node.next_sibling),
+                _ => None,
+            });
+
+            let Some(link) = link else {
+                // we're all done with this one
+                path.pop();
+                on_path.remove(&idx);
+                finished.insert(idx);
+                continue;
+            };
+            *followed += 1;
+
+            let Some(link) = link else { continue };
+
+            if on_path.contains(&link) {
+                let start = path.iter().position(|&(idx, _)| idx == link).unwrap();
+
+                return Some(path[start..].iter().map(|&(idx, _)| NodeIndex::Branch(idx)).collect());
+            }
+
+            if !finished.contains(&link) {
+                path.push((link, 0));
+                on_path.insert(link);
+            }
+        }
+
+        None
+    }
+
+    /// Returns every node in the arena which can't be reached by following child and sibling links
+    /// down from the root, in arena order.
+    ///
+    /// A graph built with this crate's methods never has any, so, like [detect_cycle], this is
+    /// only a diagnostic for graphs assembled by hand. Loops and links to nodes missing from the
+    /// arena are tolerated.
+    ///
+    /// [detect_cycle]: Self::detect_cycle
+    pub fn unreachable_nodes(&self) -> Vec<NodeIndex> {
+        let mut reached = HashSet::with_capacity(self.len());
+
+        let mut stacks = Vec::from_iter(self.root_children.map(|v| v.first));
+        while let Some(idx) = stacks.pop() {
+            let Some(node) = self.arena.get(idx) else { continue };
+            if !reached.insert(idx) {
+                continue;
+            }
+
+            stacks.extend(node.next_sibling);
+            stacks.extend(node.children.map(|v| v.first));
+        }
+
+        self.arena
+            .iter()
+            .filter(|(idx, _)| !reached.contains(idx))
+            .map(|(idx, _)| NodeIndex::Branch(idx))
+            .collect()
+    }
+
+    /// Checks that `additional` more nodes fit under the cap, if there is one.
+    fn check_capacity(&self, additional: usize) -> Result<(), SceneGraphError> {
+        match self.max_nodes {
+            Some(max_nodes) if self.len() + additional > max_nodes => Err(SceneGraphError::CapacityExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    /// Walks the graph depth first, returning every node which matches the predicate and has no
+    /// matching ancestor. The descendants of a matching node are never given to the predicate.
+    fn topmost_matching(&self, mut predicate: impl FnMut(NodeIndex, &T) -> bool) -> Vec<Index> {
+        let mut matches = Vec::new();
+
+        let mut stacks = Vec::new();
+        if let Some(children) = self.root_children {
+            stacks.push(children.first);
+        }
+
+        while let Some(idx) = stacks.pop() {
+            let node = &self.arena[idx];
+
+            if let Some(next_sibling) = node.next_sibling {
+                stacks.push(next_sibling);
+            }
+
+            if predicate(NodeIndex::Branch(idx), &node.value) {
+                matches.push(idx);
+            } else if let Some(children) = node.children {
+                stacks.push(children.first);
+            }
+        }
+
+        matches
+    }
+
+    /// Writes the subtree at `head_index`, which must exist, like `write_tree`.
+    fn write_subtree<W: std::fmt::Write>(
+        &self,
+        w: &mut W,
+        head_index: NodeIndex,
+        label: impl Fn(&T) -> String,
+    ) -> std::fmt::Result {
+        let head_value = match head_index {
+            NodeIndex::Root => &self.root,
+            NodeIndex::Branch(idx) => &self.arena[idx].value,
+        };
+        writeln!(w, "{}", label(head_value))?;
+
+        // for each ancestor beneath the head, whether it has siblings still to come
+        let mut continuing = Vec::new();
+
+        for visit in SceneGraphIterDetailed::new(self, head_index) {
+            let is_last = self.get(visit.index).unwrap().next_sibling.is_none();
+            continuing.truncate(visit.depth - 1);
+
+            for &ancestor_continues in &continuing {
+                w.write_str(if ancestor_continues { "│  " } else { "   " })?;
+            }
+            w.write_str(if is_last { "└─ " } else { "├─ " })?;
+            writeln!(w, "{}", label(visit.value))?;
+
+            continuing.push(!is_last);
+        }
+
+        Ok(())
+    }
+
+    /// Attaches a graph like `attach_graph`, but as the `position`th child of `parent`. The parent
+    /// must exist, and the graph must fit under the cap.
+    fn attach_graph_at(&mut self, parent: NodeIndex, position: usize, other_graph: SceneGraph<T>) -> NodeIndex {
+        let (new_root_idx, _) = self.attach_graph(parent, other_graph).expect("parent must exist and fit");
+
+        if let NodeIndex::Branch(idx) = new_root_idx {
+            self.unlink(idx);
+            self.place_node_at(parent, idx, position);
+        }
+
+        new_root_idx
+    }
+
+    /// Removes a node which must exist, along with all of its descendants, returning its value.
+    fn remove_subtree(&mut self, idx: Index) -> T {
+        let node = self.arena.remove(idx).unwrap();
+
+        // detach em all!
+        for _v in SceneGraphDetachIter::new(&mut self.arena, NodeIndex::Branch(idx), node.children) {}
+
+        self.fix_parent(node.next_sibling, node.last_sibling, node.parent, idx);
+
+        node.value
+    }
+
+    /// Walks every sibling chain, checking that all of the links agree with each other.
+    fn check_links(&self) -> Result<(), InvalidParts> {
+        let mut visited = HashSet::with_capacity(self.len());
+        let mut parents = vec![(NodeIndex::Root, self.root_children)];
+
+        while let Some((parent, children)) = parents.pop() {
+            let Some(children) = children else { continue };
+
+            let mut count = 0;
+            let mut last_sibling = None;
+            let mut current = Some(children.first);
+
+            while let Some(idx) = current {
+                let node = self.arena.get(idx).ok_or(InvalidParts)?;
+
+                // seeing a node twice means we've looped around
+                if !visited.insert(idx) || node.parent != parent || node.last_sibling != last_sibling {
+                    return Err(InvalidParts);
+                }
+
+                count += 1;
+                parents.push((NodeIndex::Branch(idx), node.children));
+
+                last_sibling = Some(idx);
+                current = node.next_sibling;
+            }
+
+            if last_sibling != Some(children.last) || count != children.count {
+                return Err(InvalidParts);
+            }
+        }
+
+        // anything left over is an orphan
+        if visited.len() != self.arena.len() {
+            return Err(InvalidParts);
+        }
+
+        Ok(())
+    }
+
+    /// Iterates over the indices of the given children, in sibling order.
+    fn child_indices(&self, children: Option<Children>) -> impl Iterator<Item = Index> + '_ {
+        std::iter::successors(children.map(|v| v.first), move |&idx| self.arena[idx].next_sibling)
+    }
+
+    /// Iterates over every leaf, except the root, along with its depth, in a depth first traversal.
+    fn leaves_with_depth(&self) -> impl Iterator<Item = (NodeIndex, usize)> + '_ {
+        self.iter_detailed()
+            .filter(|visit| !self.get(visit.index).unwrap().has_children())
+            .map(|visit| (visit.index, visit.depth))
+    }
+
+    /// Returns the lowest common ancestor of `a` and `b`, along with its depth.
+    fn common_ancestor(&self, mut a: NodeIndex, mut b: NodeIndex) -> Option<(NodeIndex, usize)> {
+        let mut a_depth = self.depth(a)?;
+        let mut b_depth = self.depth(b)?;
+
+        // both nodes exist, so every parent does too
+        let parent = |node_index: NodeIndex| match node_index {
+            NodeIndex::Root => unreachable!("only the root has a depth of 0"),
+            NodeIndex::Branch(idx) => self.arena[idx].parent,
+        };
+
+        // level the two out first...
+        while a_depth > b_depth {
+            a = parent(a);
+            a_depth -= 1;
+        }
+        while b_depth > a_depth {
+            b = parent(b);
+            b_depth -= 1;
+        }
+
+        // ...and then climb together until we meet
+        while a != b {
+            a = parent(a);
+            b = parent(b);
+            a_depth -= 1;
+        }
+
+        Some((a, a_depth))
+    }
+
+    /// Rewrites every arena index stored in the graph's links through `map`, for use after nodes
+    /// have been moved around in (or into a new) arena. Values aren't touched, and the nodes
+    /// themselves must already be at their new indices.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn remap_indices(&mut self, map: impl Fn(Index) -> Index) {
+        self.preorder_cache.take();
+
+        let remap_children = |children: &mut Option<Children>| {
+            if let Some(children) = children {
+                children.first = map(children.first);
+                children.last = map(children.last);
+            }
+        };
+
+        remap_children(&mut self.root_children);
+        for (_, node) in self.arena.iter_mut() {
+            if let NodeIndex::Branch(parent) = node.parent {
+                node.parent = NodeIndex::Branch(map(parent));
+            }
+            node.last_sibling = node.last_sibling.map(&map);
+            node.next_sibling = node.next_sibling.map(&map);
+            remap_children(&mut node.children);
+        }
+    }
+
+    /// Returns `true` if the subtrees headed by `a` and `b`, which must exist, have the same values
+    /// in the same shape.
+    fn subtrees_equal(&self, a: NodeIndex, b: NodeIndex) -> bool
+    where
+        T: PartialEq,
+    {
+        let head_value = |head| match head {
+            NodeIndex::Root => &self.root,
+            NodeIndex::Branch(idx) => &self.arena[idx].value,
+        };
+        let shape = |head| SceneGraphIterDetailed::new(self, head).map(|visit| (visit.depth, visit.value));
+
+        head_value(a) == head_value(b) && shape(a).eq(shape(b))
+    }
+
+    /// Swaps the values of two different nodes, which must exist.
+    fn swap_values(&mut self, a: NodeIndex, b: NodeIndex) {
+        match (a, b) {
+            (NodeIndex::Root, NodeIndex::Branch(idx)) | (NodeIndex::Branch(idx), NodeIndex::Root) => {
+                std::mem::swap(&mut self.root, &mut self.arena[idx].value);
+            }
+            (NodeIndex::Branch(a), NodeIndex::Branch(b)) => {
+                let (a, b) = self.arena.get2_mut(a, b);
+                std::mem::swap(&mut a.unwrap().value, &mut b.unwrap().value);
+            }
+            (NodeIndex::Root, NodeIndex::Root) => unreachable!("cannot swap a node with itself"),
+        }
+    }
+
+    /// Returns `true` if `idx` is `node_index` itself or one of its ancestors.
+    fn is_self_or_ancestor(&self, idx: Index, mut node_index: NodeIndex) -> bool {
+        while let NodeIndex::Branch(current) = node_index {
+            if current == idx {
+                return true;
+            }
+
+            node_index = self.arena[current].parent;
+        }
+
+        false
+    }
+
+    /// Returns how many siblings come before the given node.
+    fn sibling_position(&self, idx: Index) -> usize {
+        let mut position = 0;
+        let mut current = self.arena[idx].last_sibling;
+        while let Some(last_sibling) = current {
+            position += 1;
+            current = self.arena[last_sibling].last_sibling;
+        }
+
+        position
+    }
+
+    /// Takes a node out of its parent's children, without removing it from the arena. Its own
+    /// children are left untouched, and it keeps its (now stale) `parent`.
+    fn unlink(&mut self, idx: Index) {
+        let node = &mut self.arena[idx];
+        let next_sibling = node.next_sibling.take();
+        let last_sibling = node.last_sibling.take();
+        let parent = node.parent;
+
+        self.fix_parent(next_sibling, last_sibling, parent, idx);
+    }
+
+    /// Rewrites the sibling links of `parent`'s children so that they are in the given order, which
+    /// must hold exactly the current children of `parent`.
+    fn relink_children(&mut self, parent: NodeIndex, order: &[Index]) {
+        let (Some(&first), Some(&last)) = (order.first(), order.last()) else {
+            return;
+        };
+
+        let mut last_sibling = None;
+        for (position, &idx) in order.iter().enumerate() {
+            let node = &mut self.arena[idx];
+            node.last_sibling = last_sibling;
+            node.next_sibling = order.get(position + 1).copied();
+
+            last_sibling = Some(idx);
+        }
+
+        let children = match parent {
+            NodeIndex::Root => self.root_children.as_mut(),
+            NodeIndex::Branch(idx) => self.arena[idx].children.as_mut(),
+        }
+        .unwrap();
+
+        children.first = first;
+        children.last = last;
+
+        self.preorder_cache.take();
+    }
+
+    /// Places an unlinked node as the `position`th child of `new_parent`, which must exist. If
+    /// `position` is past the end of the children, the node is placed last.
+    fn place_node_at(&mut self, new_parent: NodeIndex, node_to_place: Index, position: usize) {
+        self.arena[node_to_place].parent = new_parent;
+
+        let parent_children = match new_parent {
+            NodeIndex::Root => self.root_children,
+            NodeIndex::Branch(idx) => self.arena[idx].children,
+        };
+
+        // find whoever is sitting in our spot right now
+        let mut current = parent_children.map(|v| v.first);
+        for _ in 0..position {
+            // stop as soon as we run off the end, so a huge `position` costs nothing extra
+            let Some(idx) = current else { break };
+            current = self.arena[idx].next_sibling;
+        }
+
+        match current {
+            Some(next_sibling) => self.place_node_before(next_sibling, node_to_place),
+            None => self.place_node(new_parent, node_to_place).expect("parent must exist"),
+        }
+    }
+
+    /// Places an unlinked node directly before `next_sibling`, under the same parent.
+    fn place_node_before(&mut self, next_sibling: Index, node_to_place: Index) {
+        self.preorder_cache.take();
+        let new_parent = self.arena[next_sibling].parent;
+        let last_sibling = self.arena[next_sibling].last_sibling.replace(node_to_place);
+
+        let node = &mut self.arena[node_to_place];
+        node.parent = new_parent;
+        node.next_sibling = Some(next_sibling);
+        node.last_sibling = last_sibling;
+
+        if let Some(last_sibling) = last_sibling {
+            self.arena[last_sibling].next_sibling = Some(node_to_place);
+        }
+
+        let parent_children = match new_parent {
+            NodeIndex::Root => self.root_children.as_mut(),
+            NodeIndex::Branch(idx) => self.arena[idx].children.as_mut(),
+        }
+        .unwrap();
+
+        parent_children.count += 1;
+        if last_sibling.is_none() {
+            // we're the new first child
+            parent_children.first = node_to_place;
+        }
+    }
+
+    /// Places a node as part of moving or attaching it.
+    fn place_node(&mut self, new_parent: NodeIndex, node_to_place: Index) -> Result<(), ParentNodeNotFound> {
+        // okay, now we gotta ATTACH ourselves back, without being monsters about it
+        let parent_children = match new_parent {
+            NodeIndex::Root => &mut self.root_children,
+            NodeIndex::Branch(idx) => &mut self.arena.get_mut(idx).ok_or(ParentNodeNotFound)?.children,
+        };
+
+        // slap ourselves in here
+        self.preorder_cache.take();
+        match parent_children.as_mut() {
+            Some(children) => {
+                let old_last = children.last;
+                children.last = node_to_place;
+                children.count += 1;
+
+                let last_sibling = &mut self.arena[old_last];
+                last_sibling.next_sibling = Some(node_to_place);
+
+                // fix this up too
+                self.arena[node_to_place].last_sibling = Some(old_last);
+            }
+            None => {
+                // this is the easy case
+                *parent_children = Some(Children {
+                    first: node_to_place,
+                    last: node_to_place,
+                    count: 1,
+                });
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Fixes a parent with a removed child.
+    fn fix_parent(
+        &mut self,
+        removed_next_sibling: Option<Index>,
+        removed_last_sibling: Option<Index>,
+        removed_parent: NodeIndex,
+        removed_idx: Index,
+    ) {
+        self.preorder_cache.take();
+
+        // fix up the parent if it was the first child...
+
+        let mut parent_children = match removed_parent {
+            NodeIndex::Root => self.root_children.unwrap(),
+            NodeIndex::Branch(idx) => self.arena[idx].children.unwrap(),
+        };
+
+        if parent_children.first == parent_children.last && parent_children.first == removed_idx {
+            match removed_parent {
+                NodeIndex::Root => self.root_children = None,
+                NodeIndex::Branch(idx) => self.arena[idx].children = None,
+            };
+        } else {
+            // extremely hard to follow the logic of this unwrap here, but if this branch is taken,
+            // then we're *never* the last child, which means we have a sibling.
+            parent_children.count -= 1;
+
+            if parent_children.first == removed_idx {
+                parent_children.first = removed_next_sibling.unwrap();
+            }
+
+            if parent_children.last == removed_idx {
+                parent_children.last = removed_last_sibling.unwrap();
+            }
+
+            if let Some(last_sibling) = removed_last_sibling {
+                let last_sibling = self.arena.get_mut(last_sibling).unwrap();
+                last_sibling.next_sibling = removed_next_sibling;
+            }
+
+            if let Some(next_sibling) = removed_next_sibling {
+                let next_sibling = self.arena.get_mut(next_sibling).unwrap();
+                next_sibling.last_sibling = removed_last_sibling;
+            }
+
+            // finally, dump our updated parent children back
+            match removed_parent {
+                NodeIndex::Root => self.root_children = Some(parent_children),
+                NodeIndex::Branch(idx) => self.arena[idx].children = Some(parent_children),
+            };
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SceneGraph<T> {
+    type Item = (&'a T, &'a T);
+
+    type IntoIter = SceneGraphIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Consuming a SceneGraph yields every value, owned, in a depth first traversal. Unlike [iter],
+/// the root comes first, at [NodeIndex::Root], so a graph with no other nodes yields just the
+/// root.
+///
+/// [iter]: SceneGraph::iter
+impl<T> IntoIterator for SceneGraph<T> {
+    type Item = (NodeIndex, T);
+
+    type IntoIter = SceneGraphIntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SceneGraphIntoIter::new(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut SceneGraph<T> {
+    type Item = (&'a mut T, &'a mut T);
+
+    type IntoIter = SceneGraphIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Indexing gives the value at a `NodeIndex`, like [SceneGraph::value], but panics if the node
+/// doesn't exist.
+impl<T> std::ops::Index<NodeIndex> for SceneGraph<T> {
+    type Output = T;
+
+    fn index(&self, node_index: NodeIndex) -> &T {
+        self.value(node_index).expect("node does not exist")
+    }
+}
+
+impl<T> std::ops::IndexMut<NodeIndex> for SceneGraph<T> {
+    fn index_mut(&mut self, node_index: NodeIndex) -> &mut T {
+        self.value_mut(node_index).expect("node does not exist")
+    }
+}
+
+/// Cloning deep copies every value. The arena is copied slot for slot, so unlike a graph rebuilt
+/// from its values (as with [SceneGraph::detach] or deserializing), every [NodeIndex] from the
+/// original refers to the same node in the clone.
+impl <T: Clone> Clone for SceneGraph<T> {
+  fn clone(&self) -> SceneGraph<T> {
+    SceneGraph {
+      root: self.root.clone(),
+      arena: self.arena.clone(),
+      root_children: self.root_children,
+      preorder_cache: self.preorder_cache.clone(),
+      max_nodes: self.max_nodes,
+    }
+  }
+}
+
+/// A wrapper around the values given to the SceneGraph. This struct includes the data on the
+/// relationships to other nodes, in addition to the value placed at the node.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct Node<T> {
+    /// The value contained within the node.
+    pub value: T,
+    parent: NodeIndex,
+    children: Option<Children>,
+    last_sibling: Option<Index>,
+    next_sibling: Option<Index>,
+    dirty: bool,
+}
+
+impl<T> Node<T> {
+    /// Makes a new node under `parent`, with no children or siblings. This is only needed when
+    /// building the raw parts of a SceneGraph by hand, where the links are then filled in with the
+    /// setters below. See [SceneGraph::from_parts].
+    pub fn new(value: T, parent: NodeIndex) -> Self {
+        Self {
+            value,
+            parent,
+            last_sibling: None,
+            next_sibling: None,
+            children: None,
+            dirty: false,
+        }
+    }
+
+    /// Returns true if this node has children.
+    pub fn has_children(&self) -> bool {
+        self.children.is_some()
+    }
+
+    /// Iterate directly over only the *direct* children of `parent_index`.
+    ///
+    /// For example, given a graph:
+    /// ROOT:
+    ///     A
+    ///         B
+    ///         C
+    ///             D
+    /// using `iter_direct_children` and passing in the `parent_index` for `A` will only yield `B`
+    /// and `C`, *not* `D`. For that kind of depth first traversal, using `iter_on_node`.
+    ///
+    /// Note: passing in a SceneGraph of a different kind than this node belongs to (but of the same
+    /// type) will create logic errors or panics.
+    pub fn iter_direct_children<'a>(&'a self, sg: &'a SceneGraph<T>) -> SceneGraphChildIter<'a, T> {
+        SceneGraphChildIter::with_children(sg, self.children.as_ref())
+    }
+
+    /// Returns the index of the parent.
+    pub fn parent(&self) -> NodeIndex {
+        self.parent
+    }
+
+    /// Returns true if this node has been marked dirty. See [SceneGraph::mark_dirty].
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the children of this node, if it has any.
+    pub fn children(&self) -> Option<Children> {
+        self.children
+    }
+
+    /// Returns the index of the sibling just before this one, if there is one.
+    pub fn last_sibling(&self) -> Option<Index> {
+        self.last_sibling
+    }
+
+    /// Returns the index of the sibling just after this one, if there is one.
+    pub fn next_sibling(&self) -> Option<Index> {
+        self.next_sibling
+    }
+
+    /// Sets the index of the parent. This only changes the link on this node, so it is only
+    /// meant for building the raw parts of a SceneGraph. See [SceneGraph::from_parts].
+    pub fn set_parent(&mut self, parent: NodeIndex) {
+        self.parent = parent;
+    }
+
+    /// Sets the children of this node. Like [set_parent], this only changes the link on this node.
+    ///
+    /// [set_parent]: Self::set_parent
+    pub fn set_children(&mut self, children: Option<Children>) {
+        self.children = children;
+    }
+
+    /// Sets the sibling just before this one. Like [set_parent], this only changes the link on
+    /// this node.
+    ///
+    /// [set_parent]: Self::set_parent
+    pub fn set_last_sibling(&mut self, last_sibling: Option<Index>) {
+        self.last_sibling = last_sibling;
+    }
+
+    /// Sets the sibling just after this one. Like [set_parent], this only changes the link on
+    /// this node.
+    ///
+    /// [set_parent]: Self::set_parent
+    pub fn set_next_sibling(&mut self, next_sibling: Option<Index>) {
+        self.next_sibling = next_sibling;
+    }
+}
+
+/// The children of a node, stored as the two ends of a linked list of siblings. This is only
+/// needed when working with the raw parts of a SceneGraph. See [SceneGraph::into_parts].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct Children {
+    /// The first child.
+    pub first: Index,
+    /// The last child.
+    pub last: Index,
+    /// The number of children.
+    pub count: usize,
+}
+
+/// A subtree removed by [SceneGraph::detach_subtree], along with where it used to be. It can be
+/// put back with [SceneGraph::reattach].
+#[derive(Debug, Clone)]
+pub struct DetachedSubtree<T> {
+    /// The subtree, where the detached node is now the *root*.
+    pub graph: SceneGraph<T>,
+    /// The index of the node's old parent.
+    pub parent: NodeIndex,
+    /// The node's old position among its siblings, where the first child is `0`.
+    pub position: usize,
+}
+
+impl<T> std::fmt::Debug for Node<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Node")
+            .field("parent", &self.parent)
+            .field("children", &self.children)
+            .field("next_sibling", &self.next_sibling)
+            .finish()
+    }
+}
+
+/// A node index into the SceneGraph.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum NodeIndex {
+    /// Signifies that the index corresponds to the root of the graph.
+    Root,
+
+    /// Signifies a non-root node.
+    Branch(thunderdome::Index),
+}
+
+impl NodeIndex {
+    /// Returns `true` if the node index is [`Root`].
+    ///
+    /// [`Root`]: NodeIndex::Root
+    #[must_use]
+    pub fn is_root(&self) -> bool {
+        matches!(self, Self::Root)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("parent node not found")]
+/// The parent node requested was not found.
+pub struct ParentNodeNotFound;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("node does not exist")]
+/// The node does not exist.
+pub struct NodeDoesNotExist;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("the parts do not form a valid scene graph")]
+/// The parts given to [SceneGraph::from_parts] were malformed.
+pub struct InvalidParts;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+/// An error from restructuring a [SceneGraph].
+pub enum SceneGraphError {
+    #[error("node does not exist")]
+    /// The node does not exist.
+    NodeDoesNotExist,
+
+    #[error("a node cannot be moved beneath itself")]
+    /// The node would have been placed beneath itself or one of its own descendants.
+    WouldCycle,
+
+    #[error("node has children")]
+    /// The node has children, but the operation requires a leaf.
+    HasChildren,
+
+    #[error("node does not have exactly one child")]
+    /// The operation requires a node with exactly one child.
+    NotExactlyOneChild,
+
+    #[error("parent node not found")]
+    /// The parent node requested was not found.
+    ParentNodeNotFound,
+
+    #[error("the graph is at capacity")]
+    /// Adding the nodes would have gone past the cap set by [SceneGraph::set_max_nodes].
+    CapacityExceeded,
+
+    #[error("the same index was given more than once")]
+    /// An operation which needs distinct nodes was given the same index more than once.
+    DuplicateIndex,
+
+    #[error("the new order is not a rearrangement of the children")]
+    /// The order given to [SceneGraph::reorder_children] didn't hold exactly the parent's
+    /// children, each once.
+    InvalidChildOrder,
+}
+
+impl From<NodeDoesNotExist> for SceneGraphError {
+    fn from(_: NodeDoesNotExist) -> Self {
+        Self::NodeDoesNotExist
+    }
+}
+
+impl From<ParentNodeNotFound> for SceneGraphError {
+    fn from(_: ParentNodeNotFound) -> Self {
+        Self::ParentNodeNotFound
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{error}")]
+/// A subtree could not be put back by [SceneGraph::reattach]. The subtree is handed back exactly
+/// as it was given, so it can be reattached elsewhere instead.
+pub struct ReattachError<T> {
+    /// Why the subtree could not be put back.
+    pub error: SceneGraphError,
+    /// The subtree which was given to [SceneGraph::reattach].
+    pub detached: DetachedSubtree<T>,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+/// The entries given to [SceneGraph::from_parent_array] did not form a tree.
+pub enum ParentArrayError {
+    #[error("no entry is the root")]
+    /// No entry had a parent of `None`.
+    NoRoot,
+
+    #[error("more than one entry is the root")]
+    /// More than one entry had a parent of `None`.
+    MultipleRoots,
+
+    #[error("entry {0} has a parent which is out of bounds")]
+    /// The entry at this position named a parent which is not in the list.
+    DanglingParent(usize),
+
+    #[error("entry {0} is not connected to the root")]
+    /// The entry at this position could not be reached from the root, which means it is part of,
+    /// or descends from, a cycle.
+    Disconnected(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_values(sg: &SceneGraph<&'static str>) -> Vec<&'static str> {
+        let mut out = vec![];
+        for (_, v) in sg.iter() {
+            out.push(*v);
+        }
+
+        out
+    }
+
+    /// Walks every sibling chain in both directions, checking that the links all agree.
+    fn assert_consistent<T>(sg: &SceneGraph<T>) {
+        let mut seen = 0;
+        let mut parents = vec![(NodeIndex::Root, sg.root_children)];
+
+        while let Some((parent, children)) = parents.pop() {
+            let Some(children) = children else { continue };
+
+            let mut count = 0;
+            let mut last_sibling = None;
+            let mut current = Some(children.first);
+            while let Some(idx) = current {
+                let node = &sg.arena[idx];
+                assert_eq!(node.parent, parent);
+                assert_eq!(node.last_sibling, last_sibling);
+
+                count += 1;
+                seen += 1;
+                parents.push((NodeIndex::Branch(idx), node.children));
+
+                last_sibling = Some(idx);
+                current = node.next_sibling;
+            }
+
+            assert_eq!(last_sibling, Some(children.last));
+            assert_eq!(count, children.count);
+        }
+
+        assert_eq!(seen, sg.len());
+    }
+
+    #[test]
+    fn basic_attach() {
+        let mut sg = SceneGraph::new("Root");
+        let root_idx = NodeIndex::Root;
+        sg.attach(root_idx, "First Child").unwrap();
+        let second_child = sg.attach(root_idx, "Second Child").unwrap();
+        sg.attach(second_child, "First Grandchild").unwrap();
+
+        assert_eq!(get_values(&sg), vec!["First Child", "Second Child", "First Grandchild"]);
+    }
+
+    #[test]
+    fn attach_internals() {
+        let mut sg = SceneGraph::new("Root");
+
+        assert_eq!(sg.root_children, None);
+
+        let root_idx = NodeIndex::Root;
+
+        let first_idx = sg.attach(root_idx, "First Child").unwrap();
+
+        // assert_eq!(sg.get_root().num_children, 1);
+        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().first), first_idx);
+        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().last), first_idx);
+
+        let second_idx = sg.attach(root_idx, "Second Child").unwrap();
+
+        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().first), first_idx);
+        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().last), second_idx);
+
+        assert_eq!(
+            sg.get(first_idx).unwrap().next_sibling.map(NodeIndex::Branch),
+            Some(second_idx)
+        );
+        assert_eq!(sg.get(first_idx).unwrap().last_sibling, None);
+
+        assert_eq!(sg.get(second_idx).unwrap().next_sibling, None);
+        assert_eq!(
+            sg.get(second_idx).unwrap().last_sibling.map(NodeIndex::Branch),
+            Some(first_idx)
+        );
+    }
+
+    #[test]
+    fn attach_after_all_children_detached() {
+        let mut sg = SceneGraph::new("Root");
+        let parent = sg.attach_at_root("Parent");
+        let first_child = sg.attach(parent, "First Child").unwrap();
+        let second_child = sg.attach(parent, "Second Child").unwrap();
+
+        sg.detach(first_child).unwrap();
+        sg.detach(second_child).unwrap();
+        assert_eq!(sg.get(parent).unwrap().children, None);
+
+        let new_child = sg.attach(parent, "New Child").unwrap();
+        let children = sg.get(parent).unwrap().children.unwrap();
+        assert_eq!(NodeIndex::Branch(children.first), new_child);
+        assert_eq!(NodeIndex::Branch(children.last), new_child);
+        assert_eq!(children.count, 1);
+
+        let new_child = sg.get(new_child).unwrap();
+        assert_eq!(new_child.last_sibling, None);
+        assert_eq!(new_child.next_sibling, None);
+
+        assert_eq!(Vec::from_iter(sg.iter_direct_children(parent).unwrap()), vec![&"New Child"]);
+        assert_consistent(&sg);
+    }
+
+    #[test]
+    fn detach_basic() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let second_child = sg.attach_at_root("Second Child");
+        let third_child = sg.attach_at_root("Third Child");
+
+        let second_child = sg.detach(second_child).unwrap();
+        assert_eq!(*second_child.root(), "Second Child");
+
+        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().first), first_child);
+        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().last), third_child);
+
+        assert_eq!(sg.get(first_child).unwrap().last_sibling, None);
+        assert_eq!(
+            sg.get(first_child).unwrap().next_sibling.map(NodeIndex::Branch),
+            Some(third_child)
+        );
+
+        assert_eq!(
+            sg.get(third_child).unwrap().last_sibling.map(NodeIndex::Branch),
+            Some(first_child)
+        );
+        assert_eq!(sg.get(third_child).unwrap().next_sibling, None);
+
+        assert_eq!(get_values(&sg), vec!["First Child", "Third Child"]);
+
+        let g = sg.attach(third_child, "First Grandchild").unwrap();
+        sg.attach(g, "Second Grandchild").unwrap();
+        let g_3 = sg.attach(g, "Third Grandchild").unwrap();
+        sg.attach(g_3, "First Greatgrandchild").unwrap();
+
+        let third_child_tree = sg.detach(third_child).unwrap();
+        assert_eq!(get_values(&sg), vec!["First Child"]);
+        assert_eq!(
+            get_values(&third_child_tree),
+            vec![
+                "First Grandchild",
+                "Second Grandchild",
+                "Third Grandchild",
+                "First Greatgrandchild"
+            ]
+        );
+        assert_eq!(*third_child_tree.root(), "Third Child");
+    }
+
+    #[test]
+    fn detach_frees_subtree_slots() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let second_child = sg.attach_at_root("Second Child");
+        let grandchild = sg.attach(second_child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let third_child = sg.attach_at_root("Third Child");
+
+        let detached = sg.detach(second_child).unwrap();
+
+        assert_eq!(sg.len(), 2);
+        for freed in [second_child, grandchild, greatgrandchild] {
+            assert!(!sg.contains(freed));
+            assert!(sg.get(freed).is_none());
+        }
+
+        // new nodes may reuse the slots, but never the old indices
+        let reused = sg.attach_at_root("Reused");
+        assert!(![second_child, grandchild, greatgrandchild].contains(&reused));
+        assert!(!sg.contains(second_child));
+
+        assert_eq!(detached.root, "Second Child");
+        assert_eq!(get_values(&detached), vec!["Grandchild", "Greatgrandchild"]);
+        assert_eq!(get_values(&sg), vec!["First Child", "Third Child", "Reused"]);
+        assert!(sg.contains(first_child) && sg.contains(third_child));
+        assert!(sg.detach(NodeIndex::Root).is_none());
+        assert_consistent(&sg);
+    }
+
+    #[test]
+    fn move_node() {
+        let mut sg = SceneGraph::new("Root");
+        let fg = sg.attach(NodeIndex::Root, "First Child").unwrap();
+        sg.attach(fg, "First Grandchild").unwrap();
+        sg.attach(fg, "Second Grandchild").unwrap();
+        sg.attach(fg, "Third Grandchild").unwrap();
+        let second_child = sg.attach(NodeIndex::Root, "Second Child").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_direct_children(fg).unwrap().cloned()),
+            vec!["First Grandchild", "Second Grandchild", "Third Grandchild",]
+        );
+
+        sg.move_node(fg, second_child).unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_direct_children(NodeIndex::Root).unwrap().cloned()),
+            vec!["Second Child",]
+        );
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_direct_children(fg).unwrap().cloned()),
+            vec!["First Grandchild", "Second Grandchild", "Third Grandchild",]
+        );
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_direct_children(second_child).unwrap().cloned()),
+            vec!["First Child",]
+        );
+    }
+
+    #[test]
+    fn move_node_into_empty_parent() {
+        let mut sg = SceneGraph::new("Root");
+        sg.attach_at_root("First Child");
+        let second_child = sg.attach_at_root("Second Child");
+        let third_child = sg.attach_at_root("Third Child");
+
+        sg.move_node(second_child, third_child).unwrap();
+
+        assert_consistent(&sg);
+        assert_eq!(get_values(&sg), vec!["First Child", "Third Child", "Second Child"]);
+    }
+
+    #[test]
+    fn move_node_as_child_at() {
+        let mut sg = SceneGraph::new("Root");
+        let mover = sg.attach_at_root("Mover");
+        sg.attach(mover, "Mover's Child").unwrap();
+        let parent = sg.attach_at_root("Parent");
+        sg.attach(parent, "A").unwrap();
+        sg.attach(parent, "B").unwrap();
+        sg.attach(parent, "C").unwrap();
+
+        let children =
+            |sg: &SceneGraph<&'static str>| Vec::from_iter(sg.iter_direct_children(parent).unwrap().cloned());
+
+        sg.move_node_as_child_at(mover, parent, 0).unwrap();
+        assert_eq!(children(&sg), vec!["Mover", "A", "B", "C"]);
+        assert_consistent(&sg);
+
+        sg.move_node_as_child_at(mover, parent, 2).unwrap();
+        assert_eq!(children(&sg), vec!["A", "B", "Mover", "C"]);
+        assert_consistent(&sg);
+
+        sg.move_node_as_child_at(mover, parent, 100).unwrap();
+        assert_eq!(children(&sg), vec!["A", "B", "C", "Mover"]);
+        assert_consistent(&sg);
+
+        assert_eq!(sg.parent(mover), Some(parent));
+        assert_eq!(
+            get_values(&sg),
+            vec!["Parent", "A", "B", "C", "Mover", "Mover's Child"]
+        );
+    }
+
+    #[test]
+    fn move_node_as_child_at_errors() {
+        let mut sg = SceneGraph::new("Root");
+        let mover = sg.attach_at_root("Mover");
+        let child = sg.attach(mover, "Child").unwrap();
+        let grandchild = sg.attach(child, "Grandchild").unwrap();
+
+        assert_eq!(sg.move_node_as_child_at(mover, mover, 0), Err(SceneGraphError::WouldCycle));
+        assert_eq!(sg.move_node_as_child_at(mover, grandchild, 0), Err(SceneGraphError::WouldCycle));
+        assert_eq!(
+            sg.move_node_as_child_at(NodeIndex::Root, child, 0),
+            Err(SceneGraphError::NodeDoesNotExist)
+        );
+
+        sg.remove(grandchild);
+        assert_eq!(
+            sg.move_node_as_child_at(mover, grandchild, 0),
+            Err(SceneGraphError::NodeDoesNotExist)
+        );
+
+        assert_consistent(&sg);
+        assert_eq!(get_values(&sg), vec!["Mover", "Child"]);
+    }
+
+    #[test]
+    fn reparent() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+        sg.attach(second_child, "Second Grandchild").unwrap();
+
+        sg.reparent(grandchild, second_child).unwrap();
+
+        assert_consistent(&sg);
+        assert_eq!(sg.parent(grandchild), Some(second_child));
+        assert_eq!(sg.parent(greatgrandchild), Some(grandchild));
+        assert_eq!(
+            get_values(&sg),
+            vec!["First Child", "Second Child", "Second Grandchild", "Grandchild", "Greatgrandchild"]
+        );
+
+        // nothing changes on failure
+        let before = get_values(&sg);
+        assert_eq!(sg.reparent(second_child, greatgrandchild), Err(SceneGraphError::WouldCycle));
+        assert_eq!(sg.reparent(grandchild, grandchild), Err(SceneGraphError::WouldCycle));
+        assert_eq!(sg.reparent(NodeIndex::Root, first_child), Err(SceneGraphError::NodeDoesNotExist));
+        assert_eq!(get_values(&sg), before);
+        assert_consistent(&sg);
+
+        sg.reparent(grandchild, NodeIndex::Root).unwrap();
+        assert_eq!(sg.parent(grandchild), Some(NodeIndex::Root));
+        assert_consistent(&sg);
+    }
+
+    #[test]
+    fn swap_subtree_with() {
+        let mut sg = SceneGraph::new("Root");
+        sg.attach_at_root("First Child");
+        let second_child = sg.attach_at_root("Second Child");
+        sg.attach(second_child, "First Grandchild").unwrap();
+        sg.attach_at_root("Third Child");
+
+        let mut other = SceneGraph::new("Other Root");
+        let other_first = other.attach_at_root("Other First Child");
+        let other_grandchild = other.attach(other_first, "Other First Grandchild").unwrap();
+        other.attach(other_grandchild, "Other Greatgrandchild").unwrap();
+        other.attach(other_first, "Other Second Grandchild").unwrap();
+
+        let (new_idx, other_new_idx) = sg.swap_subtree_with(second_child, &mut other, other_grandchild).unwrap();
+
+        assert_consistent(&sg);
+        assert_consistent(&other);
+
+        assert_eq!(
+            get_values(&sg),
+            vec!["First Child", "Other First Grandchild", "Other Greatgrandchild", "Third Child"]
+        );
+        assert_eq!(
+            get_values(&other),
+            vec![
+                "Other First Child",
+                "Second Child",
+                "First Grandchild",
+                "Other Second Grandchild"
+            ]
+        );
+
+        assert_eq!(sg.get(new_idx).unwrap().value, "Other First Grandchild");
+        assert_eq!(other.get(other_new_idx).unwrap().value, "Second Child");
+        assert_eq!(other.parent(other_new_idx), Some(other_first));
+
+        assert_eq!(
+            sg.swap_subtree_with(NodeIndex::Root, &mut other, other_first),
+            Err(SceneGraphError::NodeDoesNotExist)
+        );
+        assert_eq!(
+            sg.swap_subtree_with(second_child, &mut other, other_first),
+            Err(SceneGraphError::NodeDoesNotExist)
+        );
+    }
+
+    #[test]
+    fn child_count_stays_correct() {
+        fn walked_count(sg: &SceneGraph<&'static str>, idx: NodeIndex) -> usize {
+            sg.iter_direct_children(idx).unwrap().count()
+        }
+
+        let mut sg = SceneGraph::new("Root");
+        assert_eq!(sg.child_count(NodeIndex::Root), Some(0));
+
+        let first_child = sg.attach_at_root("First Child");
+        let second_child = sg.attach_at_root("Second Child");
+        let third_child = sg.attach_at_root("Third Child");
+        let grandchild = sg.attach(first_child, "First Grandchild").unwrap();
+        sg.attach(first_child, "Second Grandchild").unwrap();
+
+        assert_eq!(sg.child_count(NodeIndex::Root), Some(3));
+        assert_eq!(sg.child_count(first_child), Some(2));
+        assert_eq!(sg.child_count(grandchild), Some(0));
+
+        // reorder within the same parent
+        sg.move_node(first_child, NodeIndex::Root).unwrap();
+        assert_eq!(sg.child_count(NodeIndex::Root), Some(3));
+
+        sg.move_node(second_child, third_child).unwrap();
+        assert_eq!(sg.child_count(NodeIndex::Root), Some(2));
+        assert_eq!(sg.child_count(third_child), Some(1));
+
+        sg.detach(grandchild).unwrap();
+        assert_eq!(sg.child_count(first_child), Some(1));
+
+        sg.remove(third_child);
+        assert_eq!(sg.child_count(NodeIndex::Root), Some(1));
+        assert_eq!(sg.child_count(third_child), None);
+
+        for idx in [NodeIndex::Root, first_child] {
+            assert_eq!(sg.child_count(idx), Some(walked_count(&sg, idx)));
+        }
+        assert_consistent(&sg);
+    }
+
+    #[test]
+    fn clear_works() {
+        let input_node: Vec<_> = (0..50_000).map(|v| format!("Node_{}", v)).collect();
+        let mut sg = SceneGraph::new("Root");
+
+        for v in input_node.iter() {
+            sg.attach_at_root(v);
+        }
+
+        sg.clear();
+
+        assert_eq!(sg.len(), 0);
+        assert!(sg.is_empty());
+        assert!(sg.root_children.is_none());
+        assert!(sg.arena.is_empty());
+    }
+
+    #[test]
+    fn clear_keeps_root_and_invalidates_indices() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+
+        sg.clear();
+
+        assert!(sg.iter().next().is_none());
+        assert_eq!(sg.len(), 0);
+        assert_eq!(sg.root, "Root");
+        for idx in [first_child, grandchild, second_child] {
+            assert!(!sg.contains(idx));
+            assert_eq!(sg.value(idx), None);
+        }
+
+        // the graph is still perfectly usable afterwards
+        let new_child = sg.attach_at_root("New Child");
+        assert!(!sg.contains(first_child));
+        assert_eq!(sg.value(new_child), Some(&"New Child"));
+        assert_eq!(get_values(&sg), vec!["New Child"]);
+        assert_consistent(&sg);
+    }
+
+    #[test]
+    fn new_default_is_usable() {
+        let mut sg: SceneGraph<String> = SceneGraph::new_default();
+        assert_eq!(sg.root(), "");
+        assert!(sg.is_empty());
+
+        let first_child = sg.attach_at_root("First Child".to_string());
+        sg.attach(first_child, "First Grandchild".to_string()).unwrap();
+        sg.attach_at_root("Second Child".to_string());
+
+        assert_eq!(
+            Vec::from_iter(sg.iter().map(|(_parent, value)| value.as_str())),
+            vec!["First Child", "First Grandchild", "Second Child"]
+        );
+    }
+
+    #[test]
+    fn remove_matching_returns_values() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let doomed = sg.attach(first_child, "Doomed Grandchild").unwrap();
+        let doomed_child = sg.attach(doomed, "First Greatgrandchild").unwrap();
+        sg.attach(doomed, "Doomed Greatgrandchild").unwrap();
+        sg.attach(first_child, "Second Grandchild").unwrap();
+        let doomed_sibling = sg.attach_at_root("Doomed Child");
+
+        let removed = sg.remove_matching(|value| value.starts_with("Doomed"));
+
+        assert_eq!(
+            removed,
+            vec![
+                "Doomed Grandchild",
+                "First Greatgrandchild",
+                "Doomed Greatgrandchild",
+                "Doomed Child"
+            ]
+        );
+        assert_eq!(get_values(&sg), vec!["First Child", "Second Grandchild"]);
+        assert_eq!(sg.len(), 2);
+        assert!(!sg.contains(doomed));
+        assert!(!sg.contains(doomed_child));
+        assert!(!sg.contains(doomed_sibling));
+
+        assert!(sg.remove_matching(|value| value.starts_with("Doomed")).is_empty());
+    }
+
+    #[test]
+    fn from_parent_array_builds() {
+        // children listed before their parents on purpose
+        let items = vec![
+            (Some(3), "First Grandchild"),
+            (Some(2), "First Child"),
+            (None, "Root"),
+            (Some(2), "Second Child"),
+            (Some(3), "Second Grandchild"),
+        ];
+
+        let (sg, indices) = SceneGraph::from_parent_array(items).unwrap();
+
+        assert_eq!(*sg.root(), "Root");
+        assert_eq!(
+            get_values(&sg),
+            vec!["First Child", "Second Child", "First Grandchild", "Second Grandchild"]
+        );
+
+        assert_eq!(indices[2], NodeIndex::Root);
+        assert_eq!(sg.get(indices[1]).unwrap().value, "First Child");
+        assert_eq!(sg.parent(indices[1]), Some(NodeIndex::Root));
+        assert_eq!(sg.get(indices[0]).unwrap().value, "First Grandchild");
+        assert_eq!(sg.parent(indices[0]), Some(indices[3]));
+        assert_eq!(sg.parent(indices[4]), Some(indices[3]));
+    }
+
+    #[test]
+    fn from_parent_array_errors() {
+        assert_eq!(
+            SceneGraph::<&str>::from_parent_array(vec![]).unwrap_err(),
+            ParentArrayError::NoRoot
+        );
+        assert_eq!(
+            SceneGraph::from_parent_array(vec![(None, "Root"), (None, "Other Root")]).unwrap_err(),
+            ParentArrayError::MultipleRoots
+        );
+        assert_eq!(
+            SceneGraph::from_parent_array(vec![(None, "Root"), (Some(7), "Child")]).unwrap_err(),
+            ParentArrayError::DanglingParent(1)
+        );
+        assert_eq!(
+            SceneGraph::from_parent_array(vec![(None, "Root"), (Some(2), "A"), (Some(1), "B")]).unwrap_err(),
+            ParentArrayError::Disconnected(1)
+        );
+    }
+
+    #[test]
+    fn height_balance() {
+        let mut sg = SceneGraph::new("Root");
+        let left = sg.attach_at_root("Left");
+        let right = sg.attach_at_root("Right");
+        let left_left = sg.attach(left, "Left Left").unwrap();
+        sg.attach(left, "Left Right").unwrap();
+        sg.attach(right, "Right Left").unwrap();
+        sg.attach(left_left, "Left Left Left").unwrap();
+
+        assert!(sg.is_height_balanced(1));
+        assert!(!sg.is_height_balanced(0));
+
+        // make the left side much deeper than the right
+        let mut sg = SceneGraph::new("Root");
+        let left = sg.attach_at_root("Left");
+        sg.attach_at_root("Right");
+        let left_left = sg.attach(left, "Left Left").unwrap();
+        sg.attach(left_left, "Left Left Left").unwrap();
+
+        assert!(!sg.is_height_balanced(1));
+        assert!(sg.is_height_balanced(2));
+
+        // a single chain is trivially balanced
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        let grandchild = sg.attach(child, "Grandchild").unwrap();
+        sg.attach(grandchild, "Greatgrandchild").unwrap();
+
+        assert!(sg.is_height_balanced(0));
+    }
+
+    #[test]
+    fn maximal_matching_subtrees() {
+        let mut sg = SceneGraph::new("Root");
+        let a = sg.attach_at_root("match A");
+        let a1 = sg.attach(a, "match A1").unwrap();
+        sg.attach(a1, "match A1a").unwrap();
+        sg.attach(a, "match A2").unwrap();
+        let b = sg.attach_at_root("B");
+        let b1 = sg.attach(b, "match B1").unwrap();
+        let b2 = sg.attach(b, "match B2").unwrap();
+        sg.attach(b2, "B2a").unwrap();
+        let b2b = sg.attach(b2, "match B2b").unwrap();
+
+        assert_eq!(
+            sg.maximal_matching_subtrees(|value| value.starts_with("match")),
+            vec![a, b1, b2b]
+        );
+
+        assert_eq!(sg.maximal_matching_subtrees(|_| true), vec![NodeIndex::Root]);
+        assert!(sg.maximal_matching_subtrees(|_| false).is_empty());
+    }
+
+    #[test]
+    fn distinct_value_count() {
+        let mut sg = SceneGraph::new("Root");
+        assert_eq!(sg.distinct_value_count(), 1);
+
+        let a = sg.attach_at_root("A");
+        sg.attach(a, "B").unwrap();
+        sg.attach(a, "A").unwrap();
+        let b = sg.attach_at_root("B");
+        sg.attach(b, "Root").unwrap();
+        sg.attach(b, "C").unwrap();
+
+        assert_eq!(sg.distinct_value_count(), 4);
+    }
+
+    #[test]
+    fn map_subtree_in_place() {
+        let mut sg = SceneGraph::new(0);
+        let first_child = sg.attach_at_root(1);
+        let grandchild = sg.attach(first_child, 2).unwrap();
+        sg.attach(grandchild, 3).unwrap();
+        let second_child = sg.attach_at_root(4);
+
+        sg.map_subtree_in_place(first_child, |value| *value *= 10).unwrap();
+
+        assert_eq!(*sg.root(), 0);
+        assert_eq!(Vec::from_iter(sg.iter().map(|(_, v)| *v)), vec![10, 20, 30, 4]);
+
+        sg.remove(second_child);
+        assert_eq!(sg.map_subtree_in_place(second_child, |value| *value = 0), Err(NodeDoesNotExist));
+
+        sg.map_subtree_in_place(NodeIndex::Root, |value| *value += 1).unwrap();
+        assert_eq!(*sg.root(), 1);
+        assert_eq!(Vec::from_iter(sg.iter().map(|(_, v)| *v)), vec![11, 21, 31]);
+    }
+
+    #[test]
+    fn reduce_indexed_sums_subtrees() {
+        let mut sg = SceneGraph::new(1);
+        let first_child = sg.attach_at_root(2);
+        let grandchild = sg.attach(first_child, 3).unwrap();
+        let greatgrandchild = sg.attach(grandchild, 4).unwrap();
+        let second_grandchild = sg.attach(first_child, 5).unwrap();
+        let second_child = sg.attach_at_root(6);
+
+        let sums = sg.reduce_indexed(|value, children: &[i32]| value + children.iter().sum::<i32>());
+
+        assert_eq!(sums.len(), 6);
+        assert_eq!(sums[&NodeIndex::Root], 21);
+        assert_eq!(sums[&first_child], 14);
+        assert_eq!(sums[&grandchild], 7);
+        assert_eq!(sums[&greatgrandchild], 4);
+        assert_eq!(sums[&second_grandchild], 5);
+        assert_eq!(sums[&second_child], 6);
+
+        // children are handed over in sibling order
+        let orders = sg.reduce_indexed(|value, children: &[String]| format!("{}{}", value, children.concat()));
+        assert_eq!(orders[&NodeIndex::Root], "123456");
+    }
+
+    #[test]
+    fn parent_indices_encode_tree() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let first_grandchild = sg.attach(first_child, "First Grandchild").unwrap();
+        let greatgrandchild = sg.attach(first_grandchild, "First Greatgrandchild").unwrap();
+        let second_grandchild = sg.attach(first_child, "Second Grandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+
+        let (indices, parents) = sg.parent_indices();
+
+        assert_eq!(
+            indices,
+            vec![first_child, first_grandchild, greatgrandchild, second_grandchild, second_child]
+        );
+        assert_eq!(parents, vec![-1, 0, 1, 0, -1]);
+
+        for (idx, parent) in indices.iter().zip(parents.iter()) {
+            let expected = match *parent {
+                -1 => NodeIndex::Root,
+                position => indices[position as usize],
+            };
+            assert_eq!(sg.parent(*idx), Some(expected));
+        }
+    }
+
+    #[test]
+    fn remove_leaf() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "First Grandchild").unwrap();
+        sg.attach(first_child, "Second Grandchild").unwrap();
+
+        assert_eq!(sg.remove_leaf(first_child), Err(SceneGraphError::HasChildren));
+        assert_eq!(sg.remove_leaf(NodeIndex::Root), Err(SceneGraphError::NodeDoesNotExist));
+
+        assert_eq!(sg.remove_leaf(grandchild), Ok("First Grandchild"));
+        assert!(!sg.contains(grandchild));
+        assert_eq!(sg.remove_leaf(grandchild), Err(SceneGraphError::NodeDoesNotExist));
+
+        assert_consistent(&sg);
+        assert_eq!(get_values(&sg), vec!["First Child", "Second Grandchild"]);
+    }
+
+    #[test]
+    fn swap_remove_child() {
+        let mut sg = SceneGraph::new("Root");
+        sg.attach_at_root("A");
+        let b = sg.attach_at_root("B");
+        sg.attach(b, "B1").unwrap();
+        sg.attach_at_root("C");
+        let d = sg.attach_at_root("D");
+        sg.attach(d, "D1").unwrap();
+
+        assert_eq!(sg.swap_remove_child(b), Ok("B"));
+
+        assert_consistent(&sg);
+        assert_eq!(sg.child_count(NodeIndex::Root), Some(3));
+        assert_eq!(get_values(&sg), vec!["A", "D", "D1", "C"]);
+
+        // removing the last child just removes it
+        let c = sg.iter_detailed().find(|visit| *visit.value == "C").unwrap().index;
+        assert_eq!(sg.swap_remove_child(c), Ok("C"));
+        assert_consistent(&sg);
+        assert_eq!(get_values(&sg), vec!["A", "D", "D1"]);
+
+        assert_eq!(sg.swap_remove_child(NodeIndex::Root), Err(NodeDoesNotExist));
+        assert_eq!(sg.swap_remove_child(b), Err(NodeDoesNotExist));
+    }
+
+    #[test]
+    fn parts_round_trip() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        sg.attach(first_child, "First Grandchild").unwrap();
+        sg.attach(first_child, "Second Grandchild").unwrap();
+        sg.attach_at_root("Second Child");
+
+        let expected = get_values(&sg);
+
+        let (root, root_children, arena) = sg.into_parts();
+        let sg = SceneGraph::from_parts(root, root_children, arena).unwrap();
+
+        assert_eq!(*sg.root(), "Root");
+        assert_eq!(get_values(&sg), expected);
+    }
+
+    #[test]
+    fn from_parts_rejects_malformed() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "First Grandchild").unwrap();
+        let second_grandchild = sg.attach(first_child, "Second Grandchild").unwrap();
+
+        // loop the sibling chain back around on itself
+        let (root, root_children, mut arena) = sg.clone().into_parts();
+        let NodeIndex::Branch(first) = grandchild else { unreachable!() };
+        let NodeIndex::Branch(second) = second_grandchild else { unreachable!() };
+        arena[second].next_sibling = Some(first);
+        assert_eq!(
+            SceneGraph::from_parts(root, root_children, arena).unwrap_err(),
+            InvalidParts
+        );
+
+        // an orphan which nobody links to
+        let (root, root_children, mut arena) = sg.clone().into_parts();
+        arena.insert(Node::new("Orphan", NodeIndex::Root));
+        assert_eq!(
+            SceneGraph::from_parts(root, root_children, arena).unwrap_err(),
+            InvalidParts
+        );
+
+        // the root's children lie about the count
+        let (root, mut root_children, arena) = sg.into_parts();
+        root_children.as_mut().unwrap().count = 2;
+        assert_eq!(
+            SceneGraph::from_parts(root, root_children, arena).unwrap_err(),
+            InvalidParts
+        );
+    }
+
+    #[test]
+    fn dirty_nodes() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "First Grandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+
+        assert!(sg.iter_dirty().next().is_none());
+
+        sg.mark_dirty(second_child).unwrap();
+        sg.mark_dirty(grandchild).unwrap();
+        assert_eq!(sg.mark_dirty(NodeIndex::Root), Err(NodeDoesNotExist));
+
+        assert!(sg.get(grandchild).unwrap().is_dirty());
+        assert!(!sg.get(first_child).unwrap().is_dirty());
+        assert_eq!(
+            Vec::from_iter(sg.iter_dirty()),
+            vec![(grandchild, &"First Grandchild"), (second_child, &"Second Child")]
+        );
+
+        sg.clear_dirty();
+        assert!(sg.iter_dirty().next().is_none());
+    }
+
+    #[test]
+    fn collapse_into_only_child() {
+        let mut sg = SceneGraph::new("Root".to_string());
+        sg.attach_at_root("First Child".to_string());
+        let group = sg.attach_at_root("Group".to_string());
+        let only_child = sg.attach(group, "Only Child".to_string()).unwrap();
+        sg.attach(only_child, "Grandchild".to_string()).unwrap();
+        sg.attach_at_root("Third Child".to_string());
+
+        let survivor = sg
+            .collapse_into_only_child(group, |group, child| *child = format!("{} {}", group, child))
+            .unwrap();
+
+        assert_eq!(survivor, only_child);
+        assert!(!sg.contains(group));
+        assert_eq!(sg.parent(only_child), Some(NodeIndex::Root));
+        assert_consistent(&sg);
+        assert_eq!(
+            Vec::from_iter(sg.iter().map(|(_, v)| v.as_str())),
+            vec!["First Child", "Group Only Child", "Grandchild", "Third Child"]
+        );
+    }
+
+    #[test]
+    fn collapse_into_only_child_errors() {
+        let mut sg = SceneGraph::new("Root");
+        let leaf = sg.attach_at_root("Leaf");
+        let parent = sg.attach_at_root("Parent");
+        sg.attach(parent, "First Child").unwrap();
+        sg.attach(parent, "Second Child").unwrap();
+
+        assert_eq!(
+            sg.collapse_into_only_child(leaf, |_, _| {}),
+            Err(SceneGraphError::NotExactlyOneChild)
+        );
+        assert_eq!(
+            sg.collapse_into_only_child(parent, |_, _| {}),
+            Err(SceneGraphError::NotExactlyOneChild)
+        );
+        assert_eq!(
+            sg.collapse_into_only_child(NodeIndex::Root, |_, _| {}),
+            Err(SceneGraphError::NodeDoesNotExist)
+        );
+
+        assert_consistent(&sg);
+        assert_eq!(get_values(&sg), vec!["Leaf", "Parent", "First Child", "Second Child"]);
+    }
+
+    #[test]
+    fn isolate_keeps_only_subtree() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let second_child = sg.attach_at_root("Second Child");
+        let grandchild = sg.attach(second_child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let first_leaf = sg.attach(greatgrandchild, "First Leaf").unwrap();
+        let second_leaf = sg.attach(greatgrandchild, "Second Leaf").unwrap();
+        let sibling = sg.attach(grandchild, "Sibling").unwrap();
+        let third_child = sg.attach_at_root("Third Child");
+
+        sg.isolate(greatgrandchild).unwrap();
+
+        assert_consistent(&sg);
+        assert_eq!(sg.len(), 3);
+        assert_eq!(sg.parent(greatgrandchild), Some(NodeIndex::Root));
+        assert_eq!(get_values(&sg), vec!["Greatgrandchild", "First Leaf", "Second Leaf"]);
+        assert!(sg.contains(first_leaf) && sg.contains(second_leaf));
+        for removed in [first_child, second_child, grandchild, sibling, third_child] {
+            assert!(!sg.contains(removed));
+        }
+        assert_eq!(*sg.root(), "Root");
+
+        assert_eq!(sg.isolate(first_child), Err(NodeDoesNotExist));
+        sg.isolate(NodeIndex::Root).unwrap();
+        assert_eq!(sg.len(), 3);
+    }
+
+    #[test]
+    fn euler_tour_encodes_ancestry() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let second_grandchild = sg.attach(first_child, "Second Grandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+        let third_grandchild = sg.attach(second_child, "Third Grandchild").unwrap();
+
+        let times = sg.euler_tour();
+        assert_eq!(times.len(), sg.len() + 1);
+        assert_eq!(times[&NodeIndex::Root].0, 0);
+
+        let is_ancestor = |a: NodeIndex, b: NodeIndex| {
+            let (a_in, a_out) = times[&a];
+            let (b_in, b_out) = times[&b];
+
+            a_in < b_in && b_out < a_out
+        };
+
+        assert!(is_ancestor(NodeIndex::Root, greatgrandchild));
+        assert!(is_ancestor(first_child, greatgrandchild));
+        assert!(is_ancestor(grandchild, greatgrandchild));
+        assert!(is_ancestor(first_child, second_grandchild));
+        assert!(is_ancestor(second_child, third_grandchild));
+
+        assert!(!is_ancestor(greatgrandchild, first_child));
+        assert!(!is_ancestor(grandchild, second_grandchild));
+        assert!(!is_ancestor(second_grandchild, greatgrandchild));
+        assert!(!is_ancestor(first_child, third_grandchild));
+        assert!(!is_ancestor(grandchild, grandchild));
+    }
+
+    #[test]
+    fn children_windows() {
+        let mut sg = SceneGraph::new("Root");
+        let parent = sg.attach_at_root("Parent");
+        for value in ["A", "B", "C", "D", "E"] {
+            sg.attach(parent, value).unwrap();
+        }
+        let leaf = sg.attach_at_root("Leaf");
+
+        assert_eq!(
+            Vec::from_iter(sg.children_windows(parent, 3).unwrap()),
+            vec![vec![&"A", &"B", &"C"], vec![&"B", &"C", &"D"], vec![&"C", &"D", &"E"]]
+        );
+        assert_eq!(sg.children_windows(parent, 5).unwrap().count(), 1);
+        assert_eq!(sg.children_windows(parent, 6).unwrap().count(), 0);
+        assert_eq!(sg.children_windows(leaf, 1).unwrap().count(), 0);
+
+        sg.remove(leaf);
+        assert!(sg.children_windows(leaf, 1).is_err());
+    }
+
+    #[test]
+    fn detach_subtree_then_reattach() {
+        let mut sg = SceneGraph::new("Root");
+        sg.attach_at_root("First Child");
+        let second_child = sg.attach_at_root("Second Child");
+        sg.attach(second_child, "First Grandchild").unwrap();
+        let second_grandchild = sg.attach(second_child, "Second Grandchild").unwrap();
+        sg.attach(second_grandchild, "Greatgrandchild").unwrap();
+        sg.attach(second_child, "Third Grandchild").unwrap();
+        sg.attach_at_root("Third Child");
+        let before = get_values(&sg);
+
+        let detached = sg.detach_subtree(second_grandchild).unwrap();
+        assert_eq!(detached.parent, second_child);
+        assert_eq!(detached.position, 1);
+        assert!(!sg.contains(second_grandchild));
+        assert_eq!(sg.child_count(second_child), Some(2));
+
+        let new_idx = sg.reattach(detached).unwrap();
+        assert_consistent(&sg);
+        assert_eq!(get_values(&sg), before);
+        assert_eq!(sg.parent(new_idx), Some(second_child));
+
+        // the whole subtree comes back too
+        let detached = sg.detach_subtree(second_child).unwrap();
+        assert_eq!(detached.position, 1);
+        sg.reattach(detached).unwrap();
+        assert_consistent(&sg);
+        assert_eq!(get_values(&sg), before);
+
+        assert!(sg.detach_subtree(NodeIndex::Root).is_none());
+    }
+
+    #[test]
+    fn reattach_after_parent_removed() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        let grandchild = sg.attach(child, "Grandchild").unwrap();
+
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        sg.attach(grandchild, "Second Greatgrandchild").unwrap();
+        sg.attach(greatgrandchild, "Greatgreatgrandchild").unwrap();
+
+        let detached = sg.detach_subtree(grandchild).unwrap();
+        sg.remove(child);
+
+        let err = sg.reattach(detached).unwrap_err();
+        assert_eq!(err.error, SceneGraphError::ParentNodeNotFound);
+        assert!(sg.is_empty());
+
+        // we get the whole subtree back, and can put it somewhere else
+        let detached = err.detached;
+        assert_eq!(detached.parent, child);
+        assert_eq!(detached.position, 0);
+        assert_eq!(detached.graph.root, "Grandchild");
+        assert_eq!(
+            get_values(&detached.graph),
+            vec!["Greatgrandchild", "Greatgreatgrandchild", "Second Greatgrandchild"]
+        );
+        assert_consistent(&detached.graph);
+
+        let new_idx = sg.graft(NodeIndex::Root, detached.graph).unwrap();
+        assert_eq!(sg.parent(new_idx), Some(NodeIndex::Root));
+        assert_eq!(sg.len(), 4);
+    }
+
+    #[test]
+    fn leaf_descendant_count() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let first_leaf = sg.attach(grandchild, "First Leaf").unwrap();
+        sg.attach(grandchild, "Second Leaf").unwrap();
+        sg.attach(first_child, "Third Leaf").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+
+        assert_eq!(sg.leaf_descendant_count(grandchild), Some(2));
+        assert_eq!(sg.leaf_descendant_count(first_child), Some(3));
+        assert_eq!(sg.leaf_descendant_count(NodeIndex::Root), Some(4));
+        assert_eq!(sg.leaf_descendant_count(first_leaf), Some(1));
+        assert_eq!(sg.leaf_descendant_count(second_child), Some(1));
+
+        sg.remove(second_child);
+        assert_eq!(sg.leaf_descendant_count(second_child), None);
+    }
+
+    #[test]
+    fn write_tree() {
+        let mut sg = SceneGraph::new("Root");
+        let a = sg.attach_at_root("A");
+        sg.attach(a, "A1").unwrap();
+        let a2 = sg.attach(a, "A2").unwrap();
+        sg.attach(a2, "A2a").unwrap();
+        let b = sg.attach_at_root("B");
+        sg.attach(b, "B1").unwrap();
+
+        let mut output = String::new();
+        sg.write_tree(&mut output, |value| value.to_string()).unwrap();
+
+        assert_eq!(
+            output,
+            "Root\n\
+            ├─ A\n\
+            │  ├─ A1\n\
+            │  └─ A2\n\
+            │     └─ A2a\n\
+            └─ B\n\
+            \x20  └─ B1\n"
+        );
+
+        let mut output = String::new();
+        SceneGraph::new("Lonely Root")
+            .write_tree(&mut output, |value| value.to_uppercase())
+            .unwrap();
+        assert_eq!(output, "LONELY ROOT\n");
+    }
+
+    #[test]
+    fn order_children_by_weight() {
+        let mut sg = SceneGraph::new(0);
+        let light = sg.attach_at_root(1);
+        sg.attach(light, 2).unwrap();
+        sg.attach(light, 7).unwrap();
+        sg.attach(light, 5).unwrap();
+        let heavy = sg.attach_at_root(9);
+        let first_tie = sg.attach(heavy, 3).unwrap();
+        sg.attach(heavy, 8).unwrap();
+        let second_tie = sg.attach(heavy, 3).unwrap();
+        sg.attach(first_tie, 4).unwrap();
+        sg.attach(first_tie, 6).unwrap();
+        sg.attach_at_root(4);
+
+        sg.order_children_by_weight(|&value| value);
+        assert_consistent(&sg);
+
+        for parent in std::iter::once(NodeIndex::Root).chain(sg.iter_detailed().map(|visit| visit.index)) {
+            let weights = Vec::from_iter(sg.iter_direct_children(parent).unwrap().copied());
+            assert!(weights.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", weights);
+        }
+
+        assert_eq!(
+            Vec::from_iter(sg.iter().map(|(_, &value)| value)),
+            vec![9, 8, 3, 6, 4, 3, 4, 1, 7, 5, 2]
+        );
+
+        // ties stay in the order they were
+        let heavy_children = Vec::from_iter(sg.iter_detailed().filter(|v| v.parent == heavy).map(|v| v.index));
+        assert_eq!(heavy_children[1..], [first_tie, second_tie]);
+    }
+
+    #[test]
+    fn debug_subtree() {
+        let mut sg = SceneGraph::new("Root");
+        sg.attach_at_root("Before");
+        let a = sg.attach_at_root("A");
+        let a1 = sg.attach(a, "A1").unwrap();
+        sg.attach(a1, "A1a").unwrap();
+        sg.attach(a, "A2").unwrap();
+        sg.attach_at_root("After");
+
+        assert_eq!(
+            sg.debug_subtree(a).unwrap(),
+            "\"A\"\n\
+            ├─ \"A1\"\n\
+            │  └─ \"A1a\"\n\
+            └─ \"A2\"\n"
+        );
+        assert_eq!(sg.debug_subtree(a1).unwrap(), "\"A1\"\n└─ \"A1a\"\n");
+
+        sg.remove(a);
+        assert_eq!(sg.debug_subtree(a), Err(NodeDoesNotExist));
+    }
+
+    #[test]
+    fn detect_cycle() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let second_grandchild = sg.attach(first_child, "Second Grandchild").unwrap();
+        let third_grandchild = sg.attach(first_child, "Third Grandchild").unwrap();
+        sg.attach(second_grandchild, "Greatgrandchild").unwrap();
+        sg.attach_at_root("Second Child");
+
+        assert_eq!(sg.detect_cycle(), None);
+        assert_eq!(SceneGraph::new("Root").detect_cycle(), None);
+
+        // loop the sibling chain back around on itself
+        let NodeIndex::Branch(third) = third_grandchild else { unreachable!() };
+        let NodeIndex::Branch(second) = second_grandchild else { unreachable!() };
+        sg.arena[third].next_sibling = Some(second);
+
+        assert_eq!(sg.detect_cycle(), Some(vec![second_grandchild, third_grandchild]));
+
+        // and a child which points back up at its ancestor
+        sg.arena[third].next_sibling = None;
+        let NodeIndex::Branch(first) = first_child else { unreachable!() };
+        let NodeIndex::Branch(grand) = grandchild else { unreachable!() };
+        sg.arena[grand].children = Some(Children {
+            first,
+            last: first,
+            count: 1,
+        });
+
+        assert_eq!(sg.detect_cycle(), Some(vec![first_child, grandchild]));
+    }
+
+    #[test]
+    fn iter_subtree_inclusive() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_subtree_inclusive(first_child).unwrap()),
+            vec![
+                (first_child, &"First Child"),
+                (grandchild, &"Grandchild"),
+                (greatgrandchild, &"Greatgrandchild")
+            ]
+        );
+        assert_eq!(
+            Vec::from_iter(sg.iter_subtree_inclusive(NodeIndex::Root).unwrap()),
+            vec![
+                (NodeIndex::Root, &"Root"),
+                (first_child, &"First Child"),
+                (grandchild, &"Grandchild"),
+                (greatgrandchild, &"Greatgrandchild"),
+                (second_child, &"Second Child"),
+            ]
+        );
+        assert_eq!(
+            Vec::from_iter(sg.iter_subtree_inclusive(second_child).unwrap()),
+            vec![(second_child, &"Second Child")]
+        );
+
+        sg.remove(second_child);
+        assert!(sg.iter_subtree_inclusive(second_child).is_err());
+    }
+
+    #[test]
+    fn coalesce_children() {
+        let mut sg = SceneGraph::new("Root".to_string());
+        let parent = sg.attach_at_root("Parent".to_string());
+        let first_a = sg.attach(parent, "a".to_string()).unwrap();
+        sg.attach(first_a, "a1".to_string()).unwrap();
+        let second_a = sg.attach(parent, "a".to_string()).unwrap();
+        sg.attach(second_a, "a2".to_string()).unwrap();
+        sg.attach(second_a, "a3".to_string()).unwrap();
+        sg.attach(parent, "a".to_string()).unwrap();
+        let b = sg.attach(parent, "b".to_string()).unwrap();
+        let last_a = sg.attach(parent, "a".to_string()).unwrap();
+        let sibling = sg.attach_at_root("a".to_string());
+
+        sg.coalesce_children(
+            parent,
+            |previous, current| previous.starts_with(current.as_str()),
+            |previous, current| previous.push_str(&current),
+        )
+        .unwrap();
+
+        assert_consistent(&sg);
+        assert_eq!(
+            Vec::from_iter(sg.iter_direct_children(parent).unwrap().map(|v| v.as_str())),
+            vec!["aaa", "b", "a"]
+        );
+        assert_eq!(
+            Vec::from_iter(sg.iter_direct_children(first_a).unwrap().map(|v| v.as_str())),
+            vec!["a1", "a2", "a3"]
+        );
+        assert!(!sg.contains(second_a));
+        assert!(sg.contains(b) && sg.contains(last_a) && sg.contains(sibling));
+
+        assert!(sg.coalesce_children(second_a, |_, _| true, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn get_mut_keeps_iteration_order() {
+        let mut sg = SceneGraph::new(0);
+        let first_child = sg.attach_at_root(1);
+        let grandchild = sg.attach(first_child, 2).unwrap();
+        sg.attach(grandchild, 3).unwrap();
+        let second_child = sg.attach_at_root(4);
+        let second_grandchild = sg.attach(second_child, 5).unwrap();
+        sg.attach_at_root(6);
+
+        let order = Vec::from_iter(sg.iter_detailed().map(|visit| visit.index));
+        let cached = sg.preorder_indices().as_ptr();
+
+        for idx in [second_grandchild, first_child, grandchild] {
+            sg.get_mut(idx).unwrap().value *= 10;
+        }
+
+        assert_eq!(Vec::from_iter(sg.iter_detailed().map(|visit| visit.index)), order);
+        assert_eq!(Vec::from_iter(sg.iter().map(|(_, &v)| v)), vec![10, 20, 3, 4, 50, 6]);
+
+        // values aren't structure, so the cached order survives too
+        assert_eq!(sg.preorder_indices().as_ptr(), cached);
+        assert_eq!(sg.preorder_indices(), order);
+    }
+
+    #[test]
+    fn preorder_indices_are_cached() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        sg.attach(first_child, "Grandchild").unwrap();
+        sg.attach_at_root("Second Child");
+
+        let expected = Vec::from_iter(sg.iter_detailed().map(|visit| visit.index));
+        assert_eq!(sg.preorder_indices(), expected);
+
+        // nothing changed, so we get the very same list back
+        let cached = sg.preorder_indices().as_ptr();
+        assert_eq!(sg.preorder_indices().as_ptr(), cached);
+
+        sg.get_mut(first_child).unwrap().value = "Changed Child";
+        assert!(sg.preorder_cache.get().is_some());
+
+        let new_grandchild = sg.attach(first_child, "New Grandchild").unwrap();
+        assert!(sg.preorder_cache.get().is_none());
+
+        let expected = Vec::from_iter(sg.iter_detailed().map(|visit| visit.index));
+        assert_eq!(sg.preorder_indices(), expected);
+        assert_eq!(sg.preorder_indices()[2], new_grandchild);
+
+        sg.remove(first_child);
+        assert_eq!(sg.preorder_indices().len(), 1);
+    }
+
+    #[test]
+    fn lca_depth() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let second_grandchild = sg.attach(first_child, "Second Grandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+        let cousin = sg.attach(second_child, "Cousin").unwrap();
+
+        // cousins only meet at the root
+        assert_eq!(sg.lca_depth(grandchild, cousin), Some(0));
+        assert_eq!(sg.lca_depth(greatgrandchild, second_child), Some(0));
+
+        // on the same branch, the shallower node is the ancestor
+        assert_eq!(sg.lca_depth(first_child, greatgrandchild), Some(1));
+        assert_eq!(sg.lca_depth(greatgrandchild, grandchild), Some(2));
+        assert_eq!(sg.lca_depth(grandchild, grandchild), Some(2));
+        assert_eq!(sg.lca_depth(NodeIndex::Root, cousin), Some(0));
+
+        // siblings meet at their parent
+        assert_eq!(sg.lca_depth(greatgrandchild, second_grandchild), Some(1));
+
+        sg.remove(second_child);
+        assert_eq!(sg.lca_depth(cousin, grandchild), None);
+    }
+
+    #[test]
+    fn keep_top_n() {
+        let mut sg = SceneGraph::new(0);
+        let first_child = sg.attach_at_root(1);
+        let grandchild = sg.attach(first_child, 2).unwrap();
+        let important_leaf = sg.attach(grandchild, 90).unwrap();
+        sg.attach(grandchild, 3).unwrap();
+        let second_child = sg.attach_at_root(80);
+        sg.attach(second_child, 4).unwrap();
+        let third_child = sg.attach_at_root(5);
+        let important_grandchild = sg.attach(third_child, 70).unwrap();
+        sg.attach(important_grandchild, 6).unwrap();
+
+        sg.keep_top_n(3, |&value| value);
+
+        assert_consistent(&sg);
+        let remaining = HashSet::<NodeIndex>::from_iter(sg.iter_detailed().map(|visit| visit.index));
+        assert_eq!(
+            remaining,
+            HashSet::from([
+                first_child,
+                grandchild,
+                important_leaf,
+                second_child,
+                third_child,
+                important_grandchild
+            ])
+        );
+        assert_eq!(Vec::from_iter(sg.iter().map(|(_, &v)| v)), vec![1, 2, 90, 80, 5, 70]);
+
+        sg.keep_top_n(0, |&value| value);
+        assert!(sg.is_empty());
+        assert_eq!(sg.root, 0);
+    }
+
+    #[test]
+    fn to_sexpr() {
+        let sg = crate::scene_graph!("Root" => { "A" => { "A1", "A2" }, "B" });
+        assert_eq!(sg.to_sexpr(|v| v.to_string()), "(Root (A A1 A2) B)");
+
+        let sg = crate::scene_graph!("Root" => {
+            "A" => { "A1" => { "A1a" } },
+            "B" => { "B1", "B2" },
+            "C",
+        });
+        assert_eq!(sg.to_sexpr(|v| v.to_string()), "(Root (A (A1 A1a)) (B B1 B2) C)");
+
+        // leaves are bare, even the root
+        assert_eq!(SceneGraph::new("Leaf").to_sexpr(|v| v.to_string()), "Leaf");
+
+        let sg = crate::scene_graph!("my root" => { "(paren)", "say \"hi\"", "", "plain" });
+        assert_eq!(
+            sg.to_sexpr(|v| v.to_string()),
+            r#"("my root" "(paren)" "say \"hi\"" "" plain)"#
+        );
+    }
+
+    #[test]
+    fn subtree_sizes() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let first_leaf = sg.attach(grandchild, "First Leaf").unwrap();
+        sg.attach(grandchild, "Second Leaf").unwrap();
+        let second_grandchild = sg.attach(first_child, "Second Grandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+
+        let sizes = sg.subtree_sizes();
+
+        assert_eq!(sizes.len(), sg.len() + 1);
+        assert_eq!(sizes[&NodeIndex::Root], sg.len() + 1);
+        assert_eq!(sizes[&first_leaf], 1);
+        assert_eq!(sizes[&second_child], 1);
+        assert_eq!(sizes[&grandchild], 3);
+        assert_eq!(sizes[&first_child], 1 + sizes[&grandchild] + sizes[&second_grandchild]);
+    }
+
+    #[test]
+    fn replace_preserving_children() {
+        let mut sg = SceneGraph::new("Root");
+        let parent = sg.attach_at_root("Parent");
+        let first_child = sg.attach(parent, "First Child").unwrap();
+        sg.attach(first_child, "Grandchild").unwrap();
+        sg.attach(parent, "Second Child").unwrap();
+        let children_before = sg.get(parent).unwrap().children;
+
+        assert_eq!(sg.replace_preserving_children(parent, "New Parent"), Some("Parent"));
+
+        assert_eq!(sg.get(parent).unwrap().value, "New Parent");
+        assert_eq!(sg.get(parent).unwrap().children, children_before);
+        assert_eq!(
+            Vec::from_iter(sg.iter_direct_children(parent).unwrap()),
+            vec![&"First Child", &"Second Child"]
+        );
+        assert_eq!(get_values(&sg), vec!["New Parent", "First Child", "Grandchild", "Second Child"]);
+        assert_consistent(&sg);
+
+        assert_eq!(sg.replace_preserving_children(NodeIndex::Root, "New Root"), Some("Root"));
+        assert_eq!(sg.root, "New Root");
+
+        sg.remove(parent);
+        assert_eq!(sg.replace_preserving_children(parent, "Gone"), None);
+    }
+
+    #[test]
+    fn iter_with_parent_index() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+        let second_grandchild = sg.attach(second_child, "Second Grandchild").unwrap();
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_with_parent_index()),
+            vec![
+                (first_child, Some(NodeIndex::Root), &"First Child"),
+                (grandchild, Some(first_child), &"Grandchild"),
+                (greatgrandchild, Some(grandchild), &"Greatgrandchild"),
+                (second_child, Some(NodeIndex::Root), &"Second Child"),
+                (second_grandchild, Some(second_child), &"Second Grandchild"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lcrs_round_trip() {
+        let sg = crate::scene_graph!("Root" => {
+            "A" => { "A1", "A2" => { "A2a" }, "A3" },
+            "B",
+            "C" => { "C1" },
+        });
+
+        let lcrs = sg.to_lcrs();
+        assert_consistent(&lcrs);
+        assert_eq!(lcrs.root, Some("Root"));
+        for visit in lcrs.iter_detailed() {
+            assert!(lcrs.child_count(visit.index).unwrap() <= 2);
+        }
+
+        // "A1" has a sibling but no children, so it gets a placeholder on its left
+        let a1 = lcrs.iter_detailed().find(|visit| *visit.value == Some("A1")).unwrap();
+        assert_eq!(
+            Vec::from_iter(lcrs.iter_direct_children(a1.index).unwrap()),
+            vec![&None, &Some("A2")]
+        );
+
+        let decoded = SceneGraph::from_lcrs(lcrs).unwrap();
+        assert_consistent(&decoded);
+        assert_eq!(decoded.to_contiguous(), sg.to_contiguous());
+
+        let lonely = SceneGraph::from_lcrs(SceneGraph::new("Root").to_lcrs()).unwrap();
+        assert!(lonely.is_empty());
+    }
+
+    #[test]
+    fn from_lcrs_rejects_malformed() {
+        assert!(SceneGraph::<&str>::from_lcrs(SceneGraph::new(None)).is_none());
+
+        let mut lcrs = SceneGraph::new(Some("Root"));
+        lcrs.attach_at_root(Some("A"));
+        lcrs.attach_at_root(Some("B"));
+        assert!(SceneGraph::from_lcrs(lcrs).is_none());
+
+        let mut lcrs = SceneGraph::new(Some("Root"));
+        let a = lcrs.attach_at_root(Some("A"));
+        lcrs.attach(a, Some("A1")).unwrap();
+        lcrs.attach(a, None).unwrap();
+        assert!(SceneGraph::from_lcrs(lcrs).is_none());
+
+        let mut lcrs = SceneGraph::new(Some("Root"));
+        let a = lcrs.attach_at_root(Some("A"));
+        let placeholder = lcrs.attach(a, None).unwrap();
+        lcrs.attach(placeholder, Some("Hidden")).unwrap();
+        assert!(SceneGraph::from_lcrs(lcrs).is_none());
+    }
+
+    #[test]
+    fn subtree_hashes() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("Group");
+        let grandchild = sg.attach(first_child, "Item").unwrap();
+        let deep = sg.attach(grandchild, "Detail").unwrap();
+        sg.attach(first_child, "Other").unwrap();
+        let second_child = sg.attach_at_root("Group");
+        let second_grandchild = sg.attach(second_child, "Item").unwrap();
+        sg.attach(second_grandchild, "Detail").unwrap();
+        sg.attach(second_child, "Other").unwrap();
+        let leaf = sg.attach_at_root("Item");
+
+        let hashes = sg.subtree_hashes::<DefaultHasher>();
+        assert_eq!(hashes.len(), sg.len() + 1);
+        assert_eq!(hashes[&first_child], hashes[&second_child]);
+        assert_eq!(hashes[&grandchild], hashes[&second_grandchild]);
+
+        // same value, different shape
+        assert_ne!(hashes[&leaf], hashes[&grandchild]);
+
+        sg.get_mut(deep).unwrap().value = "Changed";
+        let changed = sg.subtree_hashes::<DefaultHasher>();
+        for ancestor in [deep, grandchild, first_child, NodeIndex::Root] {
+            assert_ne!(changed[&ancestor], hashes[&ancestor]);
+        }
+        assert_eq!(changed[&second_child], hashes[&second_child]);
+        assert_eq!(changed[&leaf], hashes[&leaf]);
+    }
+
+    #[test]
+    fn iter_mut_with_path() {
+        let mut sg = crate::scene_graph!("Root".to_string() => {
+            "A".to_string() => { "A1".to_string(), "A2".to_string() => { "A2a".to_string() } },
+            "B".to_string(),
+        });
+
+        let mut paths = Vec::new();
+        for (path, value) in sg.iter_mut_with_path() {
+            value.push_str(&format!("{:?}", path));
+            paths.push(path);
+        }
+
+        assert_eq!(paths, vec![vec![0], vec![0, 0], vec![0, 1], vec![0, 1, 0], vec![1]]);
+        assert_eq!(
+            Vec::from_iter(sg.iter().map(|(_, value)| value.as_str())),
+            vec!["A[0]", "A1[0, 0]", "A2[0, 1]", "A2a[0, 1, 0]", "B[1]"]
+        );
+    }
+
+    #[test]
+    fn enclosing_match() {
+        let mut sg = SceneGraph::new("Root");
+        let container = sg.attach_at_root("Container");
+        let group = sg.attach(container, "Group").unwrap();
+        let first = sg.attach(group, "First").unwrap();
+        let second = sg.attach(group, "Second").unwrap();
+        let inner = sg.attach(first, "Container").unwrap();
+        let deep = sg.attach(inner, "Deep").unwrap();
+        let other = sg.attach_at_root("Other");
+
+        let is_container = |value: &&str| *value == "Container";
+
+        // the LCA is "Group", so we climb to the container above it
+        assert_eq!(sg.enclosing_match(first, second, is_container), Some(container));
+        assert_eq!(sg.enclosing_match(deep, second, is_container), Some(container));
+
+        // the LCA itself counts
+        assert_eq!(sg.enclosing_match(deep, inner, is_container), Some(inner));
+        assert_eq!(sg.enclosing_match(deep, deep, is_container), Some(inner));
+
+        assert_eq!(sg.enclosing_match(first, other, is_container), None);
+        assert_eq!(sg.enclosing_match(first, other, |value| *value == "Root"), Some(NodeIndex::Root));
+
+        sg.remove(other);
+        assert_eq!(sg.enclosing_match(first, other, |_| true), None);
+    }
+
+    #[test]
+    fn merge_root_children() {
+        let mut sg = SceneGraph::new("Root");
+        let parent = sg.attach_at_root("Parent");
+        sg.attach(parent, "Existing").unwrap();
+
+        let other = crate::scene_graph!("Other Root" => { "A" => { "A1" }, "B", "C" => { "C1", "C2" } });
+        let merged = sg.merge_root_children(parent, other).unwrap();
+
+        assert_consistent(&sg);
+        assert_eq!(
+            Vec::from_iter(merged.iter().map(|&idx| sg.get(idx).unwrap().value)),
+            vec!["A", "B", "C"]
+        );
+        assert!(merged.iter().all(|&idx| sg.parent(idx) == Some(parent)));
+        assert_eq!(
+            get_values(&sg),
+            vec!["Parent", "Existing", "A", "A1", "B", "C", "C1", "C2"]
+        );
+        assert!(!sg.iter().any(|(_, value)| *value == "Other Root"));
+
+        sg.remove(parent);
+        assert_eq!(
+            sg.merge_root_children(parent, SceneGraph::new("Other Root")),
+            Err(SceneGraphError::ParentNodeNotFound)
+        );
+    }
+
+    #[test]
+    fn keep_depth_range() {
+        let build = || {
+            crate::scene_graph!("Root" => {
+                "A" => { "A1" => { "A1a" => { "A1a1" } }, "A2" },
+                "B",
+                "C" => { "C1" => { "C1a" } },
+            })
+        };
+        let depths = |sg: &SceneGraph<&'static str>| Vec::from_iter(sg.iter_detailed().map(|v| (*v.value, v.depth)));
+
+        let mut sg = build();
+        sg.keep_depth_range(2, 3);
+        assert_consistent(&sg);
+
+        // "A" and "C" are connectors, and "B" never reaches the band
+        assert_eq!(
+            depths(&sg),
+            vec![("A", 1), ("A1", 2), ("A1a", 3), ("A2", 2), ("C", 1), ("C1", 2), ("C1a", 3)]
+        );
+
+        let mut sg = build();
+        sg.keep_depth_range(3, 4);
+        assert_eq!(
+            depths(&sg),
+            vec![("A", 1), ("A1", 2), ("A1a", 3), ("A1a1", 4), ("C", 1), ("C1", 2), ("C1a", 3)]
+        );
+
+        let mut sg = build();
+        sg.keep_depth_range(0, 1);
+        assert_eq!(get_values(&sg), vec!["A", "B", "C"]);
+
+        let mut sg = build();
+        sg.keep_depth_range(5, 9);
+        assert!(sg.is_empty());
+
+        let mut sg = build();
+        sg.keep_depth_range(2, 1);
+        assert!(sg.is_empty());
+        assert_eq!(sg.root, "Root");
+    }
+
+    #[test]
+    fn split_children_at() {
+        let mut sg = crate::scene_graph!("Root" => {
+            "Page" => { "1", "2", "3" => { "3a" }, "4", "5" },
+            "Last",
+        });
+        let page = sg.iter_detailed().next().unwrap().index;
+        let fourth = sg.iter_detailed().find(|visit| *visit.value == "4").unwrap().index;
+
+        let next_page = sg.split_children_at(page, 2, "Next Page").unwrap();
+
+        assert_consistent(&sg);
+        assert_eq!(Vec::from_iter(sg.iter_direct_children(page).unwrap()), vec![&"1", &"2"]);
+        assert_eq!(
+            Vec::from_iter(sg.iter_direct_children(next_page).unwrap()),
+            vec![&"3", &"4", &"5"]
+        );
+        assert_eq!(sg.parent(fourth), Some(next_page));
+        assert_eq!(get_values(&sg), vec!["Page", "1", "2", "Next Page", "3", "3a", "4", "5", "Last"]);
+
+        // splitting past the end just makes an empty group
+        let empty = sg.split_children_at(page, 9, "Empty").unwrap();
+        assert_eq!(sg.child_count(empty), Some(0));
+        assert_eq!(sg.child_count(page), Some(2));
+        assert_consistent(&sg);
+
+        assert_eq!(
+            sg.split_children_at(NodeIndex::Root, 0, "Nope"),
+            Err(SceneGraphError::NodeDoesNotExist)
+        );
+    }
+
+    #[test]
+    fn iter_with_depth() {
+        let sg = crate::scene_graph!("Root" => {
+            "A" => { "A1" => { "A1a" => { "A1a1" } } },
+            "B" => { "B1", "B2" },
+            "C",
+        });
+
+        assert_eq!(
+            Vec::from_iter(sg.iter_with_depth().map(|(depth, _, value)| (depth, *value))),
+            vec![
+                (0, "A"),
+                (1, "A1"),
+                (2, "A1a"),
+                (3, "A1a1"),
+                (0, "B"),
+                (1, "B1"),
+                (1, "B2"),
+                (0, "C")
+            ]
+        );
+
+        let indices = Vec::from_iter(sg.iter_with_depth().map(|(_, idx, _)| idx));
+        assert_eq!(indices, Vec::from_iter(sg.iter_detailed().map(|visit| visit.index)));
+    }
+
+    #[test]
+    fn dissolve_promotes_direct_children() {
+        let mut sg = crate::scene_graph!("Root" => {
+            "Before",
+            "Group" => { "A" => { "A1", "A2" }, "B", "C" => { "C1" } },
+            "After",
+        });
+        let group = sg.iter_detailed().find(|visit| *visit.value == "Group").unwrap().index;
+        let a1 = sg.iter_detailed().find(|visit| *visit.value == "A1").unwrap().index;
+        let expected = Vec::from_iter(sg.iter_direct_children(group).unwrap().copied());
+
+        let promoted = sg.dissolve(group).unwrap();
+
+        assert_consistent(&sg);
+        assert!(!sg.contains(group));
+        assert_eq!(Vec::from_iter(promoted.iter().map(|&idx| sg.get(idx).unwrap().value)), expected);
+        assert!(promoted.iter().all(|&idx| sg.parent(idx) == Some(NodeIndex::Root)));
+        assert_eq!(sg.parent(a1), Some(promoted[0]));
+        assert_eq!(get_values(&sg), vec!["Before", "A", "A1", "A2", "B", "C", "C1", "After"]);
+
+        // a leaf just goes away
+        let after = sg.iter_detailed().find(|visit| *visit.value == "After").unwrap().index;
+        assert_eq!(sg.dissolve(after), Ok(vec![]));
+        assert!(!sg.contains(after));
+
+        assert_eq!(sg.dissolve(NodeIndex::Root), Err(NodeDoesNotExist));
+        assert_eq!(sg.dissolve(group), Err(NodeDoesNotExist));
+    }
+
+    #[test]
+    fn reverse_preorder_indices() {
+        let sg = crate::scene_graph!("Root" => { "A" => { "A1" => { "A1a" }, "A2" }, "B" });
+
+        let mut expected = Vec::from_iter(sg.iter_detailed().map(|visit| visit.index));
+        expected.reverse();
+        assert_eq!(sg.reverse_preorder_indices(), expected);
+
+        // children always come first
+        let order = sg.reverse_preorder_indices();
+        for (position, &idx) in order.iter().enumerate() {
+            if let Some(NodeIndex::Branch(parent)) = sg.parent(idx) {
+                assert!(order[position..].contains(&NodeIndex::Branch(parent)));
+            }
+        }
+
+        assert!(SceneGraph::new("Root").reverse_preorder_indices().is_empty());
+    }
+
+    #[test]
+    fn max_nodes_caps_attaching() {
+        let mut sg = SceneGraph::new("Root");
+        assert_eq!(sg.max_nodes(), None);
+        sg.set_max_nodes(Some(3));
+
+        let first_child = sg.attach_at_root("First Child");
+        sg.attach(first_child, "Grandchild").unwrap();
+        sg.attach(NodeIndex::Root, "Second Child").unwrap();
+        assert_eq!(sg.len(), 3);
+
+        assert_eq!(sg.attach(first_child, "Too Many"), Err(SceneGraphError::CapacityExceeded));
+        assert_eq!(sg.try_attach_at_root("Too Many"), Err(SceneGraphError::CapacityExceeded));
+        assert_eq!(
+            sg.attach_graph(first_child, SceneGraph::new("Too Many")).unwrap_err(),
+            SceneGraphError::CapacityExceeded
+        );
+        assert_eq!(
+            sg.split_children_at(first_child, 0, "Too Many"),
+            Err(SceneGraphError::CapacityExceeded)
+        );
+        assert_eq!(sg.len(), 3);
+        assert_consistent(&sg);
+
+        // a bulk attach goes in all at once or not at all
+        sg.set_max_nodes(Some(5));
+        let big = crate::scene_graph!("Big" => { "A", "B" });
+        assert_eq!(sg.attach_graph(first_child, big).unwrap_err(), SceneGraphError::CapacityExceeded);
+        let big = crate::scene_graph!("Other Root" => { "A", "B", "C" });
+        assert_eq!(
+            sg.merge_root_children(first_child, big),
+            Err(SceneGraphError::CapacityExceeded)
+        );
+        assert_eq!(sg.len(), 3);
+
+        // swapping can't sneak past the cap either
+        let mut other = crate::scene_graph!("Other Root" => { "A" => { "A1", "A2", "A3", "A4" } });
+        let other_a = other.iter_detailed().next().unwrap().index;
+        assert_eq!(
+            sg.swap_subtree_with(first_child, &mut other, other_a),
+            Err(SceneGraphError::CapacityExceeded)
+        );
+
+        sg.set_max_nodes(None);
+        sg.attach(first_child, "Plenty").unwrap();
+        sg.swap_subtree_with(first_child, &mut other, other_a).unwrap();
+        assert_eq!(sg.len(), 6);
+        assert_consistent(&sg);
+    }
+
+    #[test]
+    fn reattach_over_capacity_hands_the_subtree_back() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        sg.attach(child, "Grandchild").unwrap();
+
+        let detached = sg.detach_subtree(child).unwrap();
+        sg.set_max_nodes(Some(1));
+        sg.attach_at_root("Filler");
+
+        let err = sg.reattach(detached).unwrap_err();
+        assert_eq!(err.error, SceneGraphError::CapacityExceeded);
+        assert_eq!(sg.len(), 1);
+
+        let detached = err.detached;
+        assert_eq!(detached.graph.root, "Child");
+        assert_eq!(get_values(&detached.graph), vec!["Grandchild"]);
+
+        // with room again, the same subtree goes right back
+        sg.set_max_nodes(None);
+        let new_idx = sg.reattach(detached).unwrap();
+        assert_eq!(get_values(&sg), vec!["Child", "Grandchild", "Filler"]);
+        assert_eq!(sg.parent(new_idx), Some(NodeIndex::Root));
+        assert_consistent(&sg);
+    }
+
+    #[test]
+    fn value_frequencies() {
+        let sg = crate::scene_graph!("a" => {
+            "b" => { "a", "c" => { "b" } },
+            "b",
+            "d" => { "a" },
+        });
+
+        let frequencies = sg.value_frequencies();
+        assert_eq!(frequencies.len(), 4);
+        assert_eq!(frequencies[&"a"], 3);
+        assert_eq!(frequencies[&"b"], 3);
+        assert_eq!(frequencies[&"c"], 1);
+        assert_eq!(frequencies[&"d"], 1);
+        assert_eq!(frequencies.values().sum::<usize>(), sg.len() + 1);
+
+        let sg = SceneGraph::new(7);
+        assert_eq!(sg.value_frequencies(), HashMap::from([(&7, 1)]));
+    }
+
+    #[test]
+    fn parent_walks_up_to_root() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        let grandchild = sg.attach(child, "Grandchild").unwrap();
+
+        let mut ancestors = vec![];
+        let mut current = grandchild;
+        while let Some(parent) = sg.parent(current) {
+            ancestors.push(parent);
+            current = parent;
+        }
+        assert_eq!(ancestors, vec![child, NodeIndex::Root]);
+
+        assert_eq!(sg.parent(NodeIndex::Root), None);
+        sg.remove(grandchild);
+        assert_eq!(sg.parent(grandchild), None);
+    }
+
+    #[test]
+    fn remap_indices_rewrites_every_link() {
+        let mut sg = crate::scene_graph!("Root" => {
+            "A" => { "A1" => { "A1a" }, "A2" },
+            "B",
+            "C" => { "C1" },
+        });
+        let before = get_values(&sg);
+        let old_order = Vec::from_iter(sg.iter_detailed().map(|visit| visit.index));
+
+        // move every node into a fresh arena, in reverse and after some padding, so nothing lands
+        // where it was
+        let old_indices = Vec::from_iter(sg.arena.iter().map(|(idx, _)| idx));
+        let mut arena = Arena::new();
+        for _ in 0..old_indices.len() {
+            arena.insert(Node::new("Padding", NodeIndex::Root));
+        }
+        let mut mapping = HashMap::new();
+        for &old in old_indices.iter().rev() {
+            let node = sg.arena.remove(old).unwrap();
+            mapping.insert(old, arena.insert(node));
+        }
+        arena.retain(|_, node| node.value != "Padding");
+        sg.arena = arena;
+        sg.remap_indices(|idx| mapping[&idx]);
+
+        assert_consistent(&sg);
+        assert_eq!(get_values(&sg), before);
+        let new_order = Vec::from_iter(sg.iter_detailed().map(|visit| visit.index));
+        assert_eq!(
+            new_order,
+            Vec::from_iter(old_order.iter().map(|&old| {
+                let NodeIndex::Branch(old) = old else { unreachable!() };
+                NodeIndex::Branch(mapping[&old])
+            }))
+        );
+        assert!(old_order.iter().all(|idx| !new_order.contains(idx)));
+    }
+
+    #[test]
+    fn iter_from_node_tells_leaves_from_stale_indices() {
+        let mut sg = SceneGraph::new("Root");
+        let parent = sg.attach_at_root("Parent");
+        let leaf = sg.attach(parent, "Leaf").unwrap();
+        let stale = sg.attach_at_root("Stale");
+        sg.remove(stale);
+
+        assert_eq!(sg.iter_from_node(leaf).unwrap().count(), 0);
+        assert_eq!(
+            Vec::from_iter(sg.iter_from_node(parent).unwrap().map(|(_, value)| *value)),
+            vec!["Leaf"]
+        );
+        assert!(matches!(sg.iter_from_node(stale), Err(NodeDoesNotExist)));
+    }
+
+    #[test]
+    fn extract_shared() {
+        let sg = crate::scene_graph!("Root" => {
+            "Tree" => { "Branch" => { "Leaf" }, "Trunk" },
+            "Rock",
+            "Tree" => { "Branch" => { "Leaf" }, "Trunk" },
+            "Tree" => { "Trunk" },
+        });
+        let heads = Vec::from_iter(sg.children(NodeIndex::Root).map(|(idx, _)| idx));
+
+        let (canonical, groups) = sg.extract_shared();
+
+        // the two big trees, then their branches, leaves and trunks
+        assert_eq!(canonical.len(), 4);
+        assert_eq!(groups.len(), 4);
+
+        assert_eq!(groups[0], vec![heads[0], heads[2]]);
+        assert_eq!(canonical[0].root, "Tree");
+        assert_eq!(get_values(&canonical[0]), vec!["Branch", "Leaf", "Trunk"]);
+        assert_consistent(&canonical[0]);
+
+        assert_eq!(Vec::from_iter(groups.iter().map(|group| group.len())), vec![2, 2, 2, 3]);
+        assert_eq!(
+            Vec::from_iter(canonical.iter().map(|sg| sg.root)),
+            vec!["Tree", "Branch", "Leaf", "Trunk"]
+        );
+
+        // nothing is shared here
+        let sg = crate::scene_graph!("Root" => { "A" => { "B" }, "B" => { "A" } });
+        assert_eq!(sg.extract_shared().1, Vec::<Vec<NodeIndex>>::new());
+    }
+
+    #[test]
+    fn clone_is_independent() {
+        let mut sg = SceneGraph::new("Root".to_string());
+        let first_child = sg.attach_at_root("First Child".to_string());
+        let grandchild = sg.attach(first_child, "Grandchild".to_string()).unwrap();
+        let second_child = sg.attach_at_root("Second Child".to_string());
+
+        let values = |sg: &SceneGraph<String>| Vec::from_iter(sg.iter().map(|(_, value)| value.clone()));
+
+        let mut cloned = sg.clone();
+        assert_eq!(values(&cloned), values(&sg));
+
+        // indices carry over to the clone
+        assert_eq!(cloned.get(grandchild).unwrap().value, "Grandchild");
+        assert_eq!(cloned.parent(grandchild), Some(first_child));
+
+        cloned.root.push_str(" (cloned)");
+        cloned.get_mut(grandchild).unwrap().value.push_str(" (cloned)");
+        cloned.remove(second_child);
+        cloned.attach(first_child, "New Grandchild".to_string()).unwrap();
+        assert_consistent(&cloned);
+
+        assert_eq!(sg.root, "Root");
+        assert_eq!(values(&sg), vec!["First Child", "Grandchild", "Second Child"]);
+        assert_eq!(
+            values(&cloned),
+            vec!["First Child", "Grandchild (cloned)", "New Grandchild"]
+        );
+        assert_consistent(&sg);
+    }
+
+    #[test]
+    fn fmt_tree() {
+        let sg = crate::scene_graph!(0 => {
+            1 => { 11 => { 111 }, 12 },
+            2,
+            3 => { 31 },
+        });
+
+        assert_eq!(
+            sg.fmt_tree(),
+            "0\n\
+             ├─ 1\n\
+             │  ├─ 11\n\
+             │  │  └─ 111\n\
+             │  └─ 12\n\
+             ├─ 2\n\
+             └─ 3\n\
+             \x20  └─ 31\n"
+        );
+        assert_eq!(SceneGraph::new("Root").fmt_tree(), "Root\n");
+    }
+
+    #[test]
+    fn graft_at() {
+        let mut sg = crate::scene_graph!("Root" => { "A", "B" });
+        let other = || crate::scene_graph!("Graft" => { "Graft Child" });
+
+        let front = sg.graft_at(NodeIndex::Root, 0, other()).unwrap();
+        let NodeIndex::Branch(front_idx) = front else { unreachable!() };
+        assert_eq!(sg.root_children.unwrap().first, front_idx);
+        sg.get_mut(front).unwrap().value = "Front";
+
+        let middle = sg.graft_at(NodeIndex::Root, 2, other()).unwrap();
+        sg.get_mut(middle).unwrap().value = "Middle";
+
+        let end = sg.graft_at(NodeIndex::Root, 100, other()).unwrap();
+        sg.get_mut(end).unwrap().value = "End";
+
+        assert_eq!(
+            Vec::from_iter(sg.children(NodeIndex::Root).map(|(_, value)| *value)),
+            vec!["Front", "A", "Middle", "B", "End"]
+        );
+        assert_eq!(
+            Vec::from_iter(sg.children(middle).map(|(_, value)| *value)),
+            vec!["Graft Child"]
+        );
+        assert_consistent(&sg);
+
+        let stale = sg.attach_at_root("Stale");
+        sg.remove(stale);
+        let before = sg.len();
+        assert_eq!(sg.graft_at(stale, 0, other()), Err(SceneGraphError::ParentNodeNotFound));
+        assert_eq!(sg.len(), before);
+    }
+
+    #[test]
+    fn map() {
+        let sg = crate::scene_graph!("Root" => {
+            "A" => { "A1" => { "A1a" }, "A2" },
+            "B",
+        });
+
+        let mut order = vec![];
+        let mapped = sg.map(|value| {
+            order.push(*value);
+            value.len()
+        });
+
+        assert_eq!(order, vec!["Root", "A", "A1", "A1a", "A2", "B"]);
+        assert_eq!(mapped.root, 4);
+        assert_consistent(&mapped);
+        assert_eq!(
+            Vec::from_iter(mapped.iter_detailed().map(|visit| (visit.depth, visit.sibling_index, *visit.value))),
+            Vec::from_iter(sg.iter_detailed().map(|visit| (visit.depth, visit.sibling_index, visit.value.len())))
+        );
+    }
+
+    #[test]
+    fn branch_and_total_count() {
+        let check = |sg: &SceneGraph<&'static str>, branches: usize| {
+            assert_eq!(sg.branch_count(), branches);
+            assert_eq!(sg.branch_count(), sg.len());
+            assert_eq!(sg.total_count(), sg.branch_count() + 1);
+        };
+
+        let mut sg = SceneGraph::new("Root");
+        check(&sg, 0);
+        assert_eq!(sg.total_count(), 1);
+
+        let child = sg.attach_at_root("Child");
+        sg.attach(child, "Grandchild").unwrap();
+        sg.attach_at_root("Second Child");
+        check(&sg, 3);
+
+        sg.remove(child);
+        check(&sg, 1);
+
+        check(&crate::scene_graph!("Root" => { "A" => { "A1", "A2" }, "B" }), 4);
+    }
+
+    #[test]
+    fn len_tracks_attach_and_remove() {
+        let mut sg = SceneGraph::new("Root");
+        assert_eq!(sg.len(), 0);
+        assert!(sg.is_empty());
+
+        let child = sg.attach_at_root("Child");
+        assert_eq!(sg.len(), 1);
+        assert!(!sg.is_empty());
+
+        let grandchild = sg.attach(child, "Grandchild").unwrap();
+        sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+        assert_eq!(sg.len(), 4);
+
+        // removing takes the whole subtree with it
+        sg.remove(grandchild);
+        assert_eq!(sg.len(), 2);
+
+        sg.remove(child);
+        sg.remove(second_child);
+        assert_eq!(sg.len(), 0);
+        assert!(sg.is_empty());
+    }
+
+    #[test]
+    fn retain_indexed() {
+        let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        let grandchild = sg.attach(first_child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
+        let second_grandchild = sg.attach(first_child, "Second Grandchild").unwrap();
+        let second_child = sg.attach_at_root("Second Child");
+
+        let selection = HashSet::from([grandchild, second_child]);
+        let mut seen = vec![];
+        sg.retain_indexed(|node_index, _| {
+            seen.push(node_index);
+            !selection.contains(&node_index)
+        });
+
+        // the removed grandchild's subtree goes with it, unasked
+        assert_eq!(seen, vec![first_child, grandchild, second_grandchild, second_child]);
+        assert_eq!(get_values(&sg), vec!["First Child", "Second Grandchild"]);
+        assert!(!sg.contains(greatgrandchild));
+        assert_consistent(&sg);
+
+        sg.retain_indexed(|_, _| false);
+        assert!(sg.is_empty());
+        assert!(sg.arena.is_empty());
+    }
+
+    #[test]
+    fn value_lookup() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        let stale = sg.attach(child, "Stale").unwrap();
+        sg.remove(stale);
+
+        assert_eq!(sg.value(NodeIndex::Root), Some(&"Root"));
+        assert_eq!(sg.value(child), Some(&"Child"));
+        assert_eq!(sg.value(stale), None);
+        assert_eq!(sg.value_mut(stale), None);
+
+        *sg.value_mut(NodeIndex::Root).unwrap() = "New Root";
+        *sg.value_mut(child).unwrap() = "New Child";
+        assert_eq!(sg.root, "New Root");
+
+        assert_eq!(sg[NodeIndex::Root], "New Root");
+        assert_eq!(sg[child], "New Child");
+        sg[child] = "Newer Child";
+        assert_eq!(get_values(&sg), vec!["Newer Child"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "node does not exist")]
+    fn indexing_stale_node_panics() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        sg.remove(child);
+
+        let _ = sg[child];
+    }
+
+    #[test]
+    fn unreachable_nodes() {
+        let mut sg = crate::scene_graph!("Root" => { "A" => { "A1", "A2" }, "B" });
+        assert!(sg.unreachable_nodes().is_empty());
+        assert!(SceneGraph::new("Root").unreachable_nodes().is_empty());
+
+        let items = vec![(None, "Root"), (Some(0), "A"), (Some(1), "A1")];
+        let (built, _) = SceneGraph::from_parent_array(items).unwrap();
+        assert!(built.unreachable_nodes().is_empty());
+
+        // slip a node into the arena without linking it in
+        let orphan = sg.arena.insert(Node::new("Orphan", NodeIndex::Root));
+        let orphan_child = sg.arena.insert(Node::new("Orphan Child", NodeIndex::Branch(orphan)));
+        sg.arena[orphan].children = Some(Children {
+            first: orphan_child,
+            last: orphan_child,
+            count: 1,
+        });
+
+        assert_eq!(
+            sg.unreachable_nodes(),
+            vec![NodeIndex::Branch(orphan), NodeIndex::Branch(orphan_child)]
+        );
+    }
+
+    #[test]
+    fn reorder_children() {
+        let mut sg = SceneGraph::new("Root");
+        let a = sg.attach_at_root("A");
+        let b = sg.attach_at_root("B");
+        let c = sg.attach_at_root("C");
+        let a1 = sg.attach(a, "A1").unwrap();
+        let a2 = sg.attach(a, "A2").unwrap();
 
-            if let Some(last_sibling) = removed_last_sibling {
-                let last_sibling = self.arena.get_mut(last_sibling).unwrap();
-                last_sibling.next_sibling = removed_next_sibling;
-            }
+        sg.reorder_children(NodeIndex::Root, &[c, a, b]).unwrap();
+        assert_eq!(get_values(&sg), vec!["C", "A", "A1", "A2", "B"]);
+        assert_consistent(&sg);
 
-            if let Some(next_sibling) = removed_next_sibling {
-                let next_sibling = self.arena.get_mut(next_sibling).unwrap();
-                next_sibling.last_sibling = removed_last_sibling;
-            }
+        sg.reorder_children(a, &[a2, a1]).unwrap();
+        assert_eq!(get_values(&sg), vec!["C", "A", "A2", "A1", "B"]);
+        assert_consistent(&sg);
 
-            // finally, dump our updated parent children back
-            match removed_parent {
-                NodeIndex::Root => self.root_children = Some(parent_children),
-                NodeIndex::Branch(idx) => self.arena[idx].children = Some(parent_children),
-            };
+        // a leaf has nothing to reorder
+        sg.reorder_children(b, &[]).unwrap();
+
+        let before = get_values(&sg);
+        for bad_order in [
+            vec![c, a],
+            vec![c, a, b, b],
+            vec![c, a, a],
+            vec![c, a, a1],
+            vec![c, a, NodeIndex::Root],
+        ] {
+            assert_eq!(
+                sg.reorder_children(NodeIndex::Root, &bad_order),
+                Err(SceneGraphError::InvalidChildOrder)
+            );
         }
+        assert_eq!(sg.reorder_children(b, &[a1]), Err(SceneGraphError::InvalidChildOrder));
+        assert_eq!(get_values(&sg), before);
+        assert_consistent(&sg);
+
+        sg.remove(c);
+        assert_eq!(sg.reorder_children(c, &[]), Err(SceneGraphError::NodeDoesNotExist));
     }
-}
 
-impl<'a, T> IntoIterator for &'a SceneGraph<T> {
-    type Item = (&'a T, &'a T);
+    #[test]
+    fn attach_at() {
+        let mut sg = crate::scene_graph!("Root" => { "B", "D" });
 
-    type IntoIter = SceneGraphIter<'a, T>;
+        let a = sg.attach_at(NodeIndex::Root, "A", 0).unwrap();
+        let NodeIndex::Branch(a_idx) = a else { unreachable!() };
+        assert_eq!(sg.root_children.unwrap().first, a_idx);
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
-    }
-}
+        sg.attach_at(NodeIndex::Root, "C", 2).unwrap();
+        let e = sg.attach_at(NodeIndex::Root, "E", 10).unwrap();
+        let NodeIndex::Branch(e_idx) = e else { unreachable!() };
+        assert_eq!(sg.root_children.unwrap().last, e_idx);
 
-impl<'a, T> IntoIterator for &'a mut SceneGraph<T> {
-    type Item = (&'a mut T, &'a mut T);
+        assert_eq!(get_values(&sg), vec!["A", "B", "C", "D", "E"]);
+        assert_consistent(&sg);
 
-    type IntoIter = SceneGraphIterMut<'a, T>;
+        // into an empty child list
+        let only = sg.attach_at(a, "Only", 3).unwrap();
+        assert_eq!(sg.parent(only), Some(a));
+        assert_eq!(get_values(&sg), vec!["A", "Only", "B", "C", "D", "E"]);
+        assert_consistent(&sg);
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter_mut()
+        let before = sg.len();
+        sg.remove(e);
+        assert_eq!(sg.attach_at(e, "Orphan", 0), Err(SceneGraphError::ParentNodeNotFound));
+        assert_eq!(sg.len(), before - 1);
     }
-}
 
-impl <T: Clone> Clone for SceneGraph<T> {
-  fn clone(&self) -> SceneGraph<T> {
-    SceneGraph {
-      root: self.root.clone(),
-      arena: self.arena.clone(),
-      root_children: self.root_children.clone(),
+    #[test]
+    fn rotate_values() {
+        let mut sg = SceneGraph::new("Root");
+        let a = sg.attach_at_root("A");
+        let b = sg.attach(a, "B").unwrap();
+        let c = sg.attach_at_root("C");
+        let structure = Vec::from_iter(sg.iter_detailed().map(|visit| (visit.index, visit.depth, visit.parent)));
+
+        sg.rotate_values(&[a, b, c]).unwrap();
+        assert_eq!((sg[a], sg[b], sg[c]), ("C", "A", "B"));
+        assert_eq!(
+            Vec::from_iter(sg.iter_detailed().map(|visit| (visit.index, visit.depth, visit.parent))),
+            structure
+        );
+        assert_consistent(&sg);
+
+        sg.rotate_values(&[NodeIndex::Root, c]).unwrap();
+        assert_eq!((sg.root, sg[c]), ("B", "Root"));
+
+        // one or no nodes have nowhere to go
+        sg.rotate_values(&[a]).unwrap();
+        sg.rotate_values(&[]).unwrap();
+        assert_eq!((sg[a], sg[b]), ("C", "A"));
+
+        let stale = sg.attach_at_root("Stale");
+        sg.remove(stale);
+        assert_eq!(sg.rotate_values(&[a, stale, b]), Err(SceneGraphError::NodeDoesNotExist));
+        assert_eq!(sg.rotate_values(&[a, b, a]), Err(SceneGraphError::DuplicateIndex));
+        assert_eq!((sg[a], sg[b], sg[c]), ("C", "A", "Root"));
     }
-  }
-}
 
-/// A wrapper around the values given to the SceneGraph. This struct includes the data on the
-/// relationships to other nodes, in addition to the value placed at the node.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
-pub struct Node<T> {
-    /// The value contained within the node.
-    pub value: T,
-    parent: NodeIndex,
-    children: Option<Children>,
-    last_sibling: Option<Index>,
-    next_sibling: Option<Index>,
-}
+    #[test]
+    fn retain() {
+        let mut sg = SceneGraph::new("Root");
+        let kept = sg.attach_at_root("Keep");
+        let kept_child = sg.attach(kept, "Keep Child").unwrap();
+        let pruned_child = sg.attach(kept, "Prune Child").unwrap();
+        let pruned = sg.attach_at_root("Prune");
+        let would_keep = sg.attach(pruned, "Keep, but beneath a pruned node").unwrap();
+        sg.attach_at_root("Keep Too");
 
-impl<T> Node<T> {
-    fn new(value: T, parent: NodeIndex) -> Self {
-        Self {
-            value,
-            parent,
-            last_sibling: None,
-            next_sibling: None,
-            children: None,
+        sg.retain(|value| value.starts_with("Keep"));
+
+        assert_eq!(get_values(&sg), vec!["Keep", "Keep Child", "Keep Too"]);
+        assert_eq!(sg.parent(kept_child), Some(kept));
+        for idx in [pruned_child, pruned, would_keep] {
+            assert!(!sg.contains(idx));
         }
+        assert_eq!(sg.len(), 3);
+        assert_consistent(&sg);
     }
 
-    /// Returns true if this node has children.
-    pub fn has_children(&self) -> bool {
-        self.children.is_some()
-    }
+    #[test]
+    fn deepest_and_shallowest_leaf() {
+        let mut sg = SceneGraph::new("Root");
+        assert_eq!(sg.deepest_leaf(), None);
+        assert_eq!(sg.shallowest_leaf(), None);
 
-    /// Iterate directly over only the *direct* children of `parent_index`.
-    ///
-    /// For example, given a graph:
-    /// ROOT:
-    ///     A
-    ///         B
-    ///         C
-    ///             D
-    /// using `iter_direct_children` and passing in the `parent_index` for `A` will only yield `B`
-    /// and `C`, *not* `D`. For that kind of depth first traversal, using `iter_on_node`.
-    ///
-    /// Note: passing in a SceneGraph of a different kind than this node belongs to (but of the same
-    /// type) will create logic errors or panics.
-    pub fn iter_direct_children<'a>(&'a self, sg: &'a SceneGraph<T>) -> SceneGraphChildIter<'a, T> {
-        SceneGraphChildIter::with_children(sg, self.children.as_ref())
-    }
+        let a = sg.attach_at_root("A");
+        let a1 = sg.attach(a, "A1").unwrap();
+        let a1a = sg.attach(a1, "A1a").unwrap();
+        let a2 = sg.attach(a, "A2").unwrap();
+        let a2a = sg.attach(a2, "A2a").unwrap();
+        let b = sg.attach_at_root("B");
+        let c = sg.attach_at_root("C");
+        sg.attach(c, "C1").unwrap();
 
-    /// Returns the index of the parent.
-    pub fn parent(&self) -> NodeIndex {
-        self.parent
-    }
-}
+        // A1a and A2a tie, so the first one wins
+        assert_eq!(sg.deepest_leaf(), Some((a1a, 3)));
+        assert_eq!(sg.shallowest_leaf(), Some((b, 1)));
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
-struct Children {
-    first: Index,
-    last: Index,
-}
+        sg.remove(b);
+        assert_eq!(sg.shallowest_leaf().map(|(_, depth)| depth), Some(2));
 
-impl<T> std::fmt::Debug for Node<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Node")
-            .field("parent", &self.parent)
-            .field("children", &self.children)
-            .field("next_sibling", &self.next_sibling)
-            .finish()
+        sg.attach(a2a, "A2a1").unwrap();
+        assert_eq!(sg.deepest_leaf().map(|(_, depth)| depth), Some(4));
     }
-}
 
-/// A node index into the SceneGraph.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
-pub enum NodeIndex {
-    /// Signifies that the index corresponds to the root of the graph.
-    Root,
+    #[test]
+    fn attach_to_stale_parent_adds_nothing() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        assert!(sg.contains(NodeIndex::Root));
+        assert!(sg.contains(child));
 
-    /// Signifies a non-root node.
-    Branch(thunderdome::Index),
-}
+        sg.remove(child);
+        assert!(!sg.contains(child));
 
-impl NodeIndex {
-    /// Returns `true` if the node index is [`Root`].
-    ///
-    /// [`Root`]: NodeIndex::Root
-    #[must_use]
-    pub fn is_root(&self) -> bool {
-        matches!(self, Self::Root)
+        // the slot gets reused, but the old index still knows it's stale
+        let reused = sg.attach_at_root("Reused");
+        assert!(!sg.contains(child));
+        assert!(sg.contains(reused));
+
+        assert_eq!(sg.attach(child, "Orphan"), Err(SceneGraphError::ParentNodeNotFound));
+        assert_eq!(sg.len(), 1);
+        assert!(sg.unreachable_nodes().is_empty());
+        assert_consistent(&sg);
     }
-}
 
-#[derive(Debug, PartialEq, Eq, thiserror::Error)]
-#[error("parent node not found")]
-/// The parent node requested was not found.
-pub struct ParentNodeNotFound;
+    #[test]
+    fn get_disjoint_mut() {
+        let mut sg = SceneGraph::new(String::from("Root"));
+        let left = sg.attach_at_root(String::from("Left"));
+        let right = sg.attach_at_root(String::from("Right"));
 
-#[derive(Debug, PartialEq, Eq, thiserror::Error)]
-#[error("node does not exist")]
-/// The node does not exist.
-pub struct NodeDoesNotExist;
+        let [left_value, right_value] = sg.get_disjoint_mut([left, right]).unwrap();
+        std::mem::swap(left_value, right_value);
+        left_value.push('!');
+        assert_eq!(sg[left], "Right!");
+        assert_eq!(sg[right], "Left");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let [root_value, right_value] = sg.get_disjoint_mut([NodeIndex::Root, right]).unwrap();
+        root_value.push_str(" and ");
+        root_value.push_str(right_value);
+        assert_eq!(sg.root, "Root and Left");
 
-    fn get_values(sg: &SceneGraph<&'static str>) -> Vec<&'static str> {
-        let mut out = vec![];
-        for (_, v) in sg.iter() {
-            out.push(*v);
-        }
+        let middle = sg.attach_at_root(String::from("Middle"));
+        let [root_value, left_value, middle_value, right_value] =
+            sg.get_disjoint_mut([NodeIndex::Root, left, middle, right]).unwrap();
+        std::mem::swap(root_value, middle_value);
+        left_value.push_str(right_value);
+        assert_eq!(sg[middle], "Root and Left");
+        assert_eq!(sg.root, "Middle");
+        assert_eq!(sg[left], "Right!Left");
+        sg.remove(middle);
 
-        out
+        assert!(sg.get_disjoint_mut([left, right, left]).is_none());
+        assert!(sg.get_disjoint_mut([NodeIndex::Root, NodeIndex::Root]).is_none());
+        sg.remove(right);
+        assert!(sg.get_disjoint_mut([left, right]).is_none());
+        assert!(sg.get_disjoint_mut([]).is_some());
     }
 
     #[test]
-    fn basic_attach() {
+    fn lowest_common_ancestor() {
         let mut sg = SceneGraph::new("Root");
-        let root_idx = NodeIndex::Root;
-        sg.attach(root_idx, "First Child").unwrap();
-        let second_child = sg.attach(root_idx, "Second Child").unwrap();
-        sg.attach(second_child, "First Grandchild").unwrap();
+        let a = sg.attach_at_root("A");
+        let a1 = sg.attach(a, "A1").unwrap();
+        let a1a = sg.attach(a1, "A1a").unwrap();
+        let a2 = sg.attach(a, "A2").unwrap();
+        let b = sg.attach_at_root("B");
 
-        assert_eq!(get_values(&sg), vec!["First Child", "Second Child", "First Grandchild"]);
+        // cousins meet at their shared grandparent
+        assert_eq!(sg.lowest_common_ancestor(a1a, a2), Some(a));
+        assert_eq!(sg.lowest_common_ancestor(a2, a1a), Some(a));
+
+        // an ancestor is its own answer
+        assert_eq!(sg.lowest_common_ancestor(a, a1a), Some(a));
+        assert_eq!(sg.lowest_common_ancestor(a1a, a1), Some(a1));
+        assert_eq!(sg.lowest_common_ancestor(a1, a1), Some(a1));
+        assert_eq!(sg.lowest_common_ancestor(NodeIndex::Root, a1a), Some(NodeIndex::Root));
+
+        // children of the root meet at the root
+        assert_eq!(sg.lowest_common_ancestor(a, b), Some(NodeIndex::Root));
+        assert_eq!(sg.lowest_common_ancestor(a1a, b), Some(NodeIndex::Root));
+
+        sg.remove(b);
+        assert_eq!(sg.lowest_common_ancestor(a, b), None);
+        assert_eq!(sg.lowest_common_ancestor(b, a), None);
     }
 
     #[test]
-    fn attach_internals() {
+    fn path_between() {
         let mut sg = SceneGraph::new("Root");
+        let a = sg.attach_at_root("A");
+        let a1 = sg.attach(a, "A1").unwrap();
+        let a1a = sg.attach(a1, "A1a").unwrap();
+        let a2 = sg.attach(a, "A2").unwrap();
+        let a2a = sg.attach(a2, "A2a").unwrap();
+        let b = sg.attach_at_root("B");
 
-        assert_eq!(sg.root_children, None);
+        // cousin to cousin passes through their grandparent once
+        assert_eq!(sg.path_between(a1a, a2a), Some(vec![a1a, a1, a, a2, a2a]));
+        assert_eq!(sg.path_between(a2a, a1a), Some(vec![a2a, a2, a, a1, a1a]));
 
-        let root_idx = NodeIndex::Root;
+        assert_eq!(sg.path_between(a1a, b), Some(vec![a1a, a1, a, NodeIndex::Root, b]));
+        assert_eq!(sg.path_between(a, a1a), Some(vec![a, a1, a1a]));
+        assert_eq!(sg.path_between(a1a, a), Some(vec![a1a, a1, a]));
+        assert_eq!(sg.path_between(a2, a2), Some(vec![a2]));
 
-        let first_idx = sg.attach(root_idx, "First Child").unwrap();
+        sg.remove(b);
+        assert_eq!(sg.path_between(a, b), None);
+    }
 
-        // assert_eq!(sg.get_root().num_children, 1);
-        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().first), first_idx);
-        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().last), first_idx);
+    #[test]
+    fn depth() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        let grandchild = sg.attach(child, "Grandchild").unwrap();
+        let greatgrandchild = sg.attach(grandchild, "Greatgrandchild").unwrap();
 
-        let second_idx = sg.attach(root_idx, "Second Child").unwrap();
+        assert_eq!(sg.depth(NodeIndex::Root), Some(0));
+        assert_eq!(sg.depth(child), Some(1));
+        assert_eq!(sg.depth(grandchild), Some(2));
+        for visit in sg.iter_detailed() {
+            assert_eq!(sg.depth(visit.index), Some(visit.depth));
+        }
 
-        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().first), first_idx);
-        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().last), second_idx);
+        sg.remove(grandchild);
+        assert_eq!(sg.depth(grandchild), None);
+        assert_eq!(sg.depth(greatgrandchild), None);
+    }
 
-        assert_eq!(
-            sg.get(first_idx).unwrap().next_sibling.map(NodeIndex::Branch),
-            Some(second_idx)
-        );
-        assert_eq!(sg.get(first_idx).unwrap().last_sibling, None);
+    #[test]
+    fn clone_subtree() {
+        let mut sg = crate::scene_graph!("Root" => {
+            "A" => { "A1" => { "A1a" }, "A2", "A3" },
+            "B",
+        });
+        let a = sg.children(NodeIndex::Root).next().unwrap().0;
 
-        assert_eq!(sg.get(second_idx).unwrap().next_sibling, None);
-        assert_eq!(
-            sg.get(second_idx).unwrap().last_sibling.map(NodeIndex::Branch),
-            Some(first_idx)
-        );
+        let template = sg.clone_subtree(a).unwrap();
+        assert_eq!(template.root, "A");
+        assert_eq!(get_values(&template), vec!["A1", "A1a", "A2", "A3"]);
+        assert_consistent(&template);
+
+        // the source is untouched, and doesn't share anything with the copy
+        assert_eq!(get_values(&sg), vec!["A", "A1", "A1a", "A2", "A3", "B"]);
+        sg[a] = "Changed";
+        assert_eq!(template.root, "A");
+
+        let whole = sg.clone_subtree(NodeIndex::Root).unwrap();
+        assert_eq!(whole.root, "Root");
+        assert_eq!(get_values(&whole), get_values(&sg));
+        assert_consistent(&whole);
+
+        sg.remove(a);
+        assert!(sg.clone_subtree(a).is_none());
     }
 
     #[test]
-    fn detach_basic() {
-        let mut sg = SceneGraph::new("Root");
-        let first_child = sg.attach_at_root("First Child");
-        let second_child = sg.attach_at_root("Second Child");
-        let third_child = sg.attach_at_root("Third Child");
+    fn graft() {
+        let mut sg = crate::scene_graph!("Root" => { "A" => { "A1" }, "B" });
+        let a = sg.children(NodeIndex::Root).next().unwrap().0;
+        let prefab = crate::scene_graph!("Prefab" => { "Arm" => { "Hand" }, "Leg" => { "Foot" } });
 
-        let second_child = sg.detach(second_child).unwrap();
-        assert_eq!(*second_child.root(), "Second Child");
-
-        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().first), first_child);
-        assert_eq!(NodeIndex::Branch(sg.root_children.unwrap().last), third_child);
+        let grafted = sg.graft(a, prefab).unwrap();
 
-        assert_eq!(sg.get(first_child).unwrap().last_sibling, None);
+        assert_eq!(sg.parent(grafted), Some(a));
+        assert_eq!(sg[grafted], "Prefab");
         assert_eq!(
-            sg.get(first_child).unwrap().next_sibling.map(NodeIndex::Branch),
-            Some(third_child)
+            Vec::from_iter(sg.iter_detailed().map(|visit| (*visit.value, visit.depth))),
+            vec![
+                ("A", 1),
+                ("A1", 2),
+                ("Prefab", 2),
+                ("Arm", 3),
+                ("Hand", 4),
+                ("Leg", 3),
+                ("Foot", 4),
+                ("B", 1)
+            ]
         );
+        assert_consistent(&sg);
 
+        sg.remove(a);
+        let before = sg.len();
         assert_eq!(
-            sg.get(third_child).unwrap().last_sibling.map(NodeIndex::Branch),
-            Some(first_child)
+            sg.graft(a, SceneGraph::new("Lost")),
+            Err(SceneGraphError::ParentNodeNotFound)
         );
-        assert_eq!(sg.get(third_child).unwrap().next_sibling, None);
+        assert_eq!(sg.len(), before);
+        assert!(sg.unreachable_nodes().is_empty());
+    }
 
-        assert_eq!(get_values(&sg), vec!["First Child", "Third Child"]);
+    #[test]
+    fn leaves() {
+        let sg = SceneGraph::new("Root");
+        assert!(sg.leaves().next().is_none());
 
-        let g = sg.attach(third_child, "First Grandchild").unwrap();
-        sg.attach(g, "Second Grandchild").unwrap();
-        let g_3 = sg.attach(g, "Third Grandchild").unwrap();
-        sg.attach(g_3, "First Greatgrandchild").unwrap();
+        let sg = crate::scene_graph!("Root" => {
+            "A" => { "A1" => { "A1a" }, "A2" },
+            "B",
+            "C" => { "C1" },
+        });
 
-        let third_child_tree = sg.detach(third_child).unwrap();
-        assert_eq!(get_values(&sg), vec!["First Child"]);
         assert_eq!(
-            get_values(&third_child_tree),
-            vec![
-                "First Grandchild",
-                "Second Grandchild",
-                "Third Grandchild",
-                "First Greatgrandchild"
-            ]
+            Vec::from_iter(sg.leaves().map(|(_, value)| *value)),
+            vec!["A1a", "A2", "B", "C1"]
         );
-        assert_eq!(*third_child_tree.root(), "Third Child");
+        for (idx, _) in sg.leaves() {
+            assert!(!sg.get(idx).unwrap().has_children());
+        }
     }
 
     #[test]
-    fn move_node() {
+    fn find() {
         let mut sg = SceneGraph::new("Root");
-        let fg = sg.attach(NodeIndex::Root, "First Child").unwrap();
-        sg.attach(fg, "First Grandchild").unwrap();
-        sg.attach(fg, "Second Grandchild").unwrap();
-        sg.attach(fg, "Third Grandchild").unwrap();
-        let second_child = sg.attach(NodeIndex::Root, "Second Child").unwrap();
+        let a = sg.attach_at_root("A");
+        let a1 = sg.attach(a, "A1").unwrap();
+        let target = sg.attach(a1, "Target").unwrap();
+        sg.attach_at_root("Target");
 
-        assert_eq!(
-            Vec::from_iter(sg.iter_direct_children(fg).unwrap().cloned()),
-            vec!["First Grandchild", "Second Grandchild", "Third Grandchild",]
-        );
+        // the first in a depth first traversal wins, however deep it is
+        assert_eq!(sg.find(|value| *value == "Target"), Some((target, &"Target")));
+        assert_eq!(sg.find(|value| value.starts_with('A')), Some((a, &"A")));
+        assert_eq!(sg.find(|value| *value == "Root"), None);
+        assert_eq!(sg.find(|value| *value == "Missing"), None);
 
-        sg.move_node(fg, second_child).unwrap();
+        let mut checked = 0;
+        sg.find(|value| {
+            checked += 1;
+            *value == "A1"
+        });
+        assert_eq!(checked, 2);
 
-        assert_eq!(
-            Vec::from_iter(sg.iter_direct_children(NodeIndex::Root).unwrap().cloned()),
-            vec!["Second Child",]
-        );
+        let (found, value) = sg.find_mut(|value| *value == "Target").unwrap();
+        assert_eq!(found, target);
+        *value = "Found";
+        assert_eq!(get_values(&sg), vec!["A", "A1", "Found", "Target"]);
+        assert!(sg.find_mut(|value| *value == "Missing").is_none());
+    }
 
-        assert_eq!(
-            Vec::from_iter(sg.iter_direct_children(fg).unwrap().cloned()),
-            vec!["First Grandchild", "Second Grandchild", "Third Grandchild",]
-        );
+    #[test]
+    fn replace() {
+        let mut sg = SceneGraph::new("Root");
+        let child = sg.attach_at_root("Child");
+        let grandchild = sg.attach(child, "Grandchild").unwrap();
 
-        assert_eq!(
-            Vec::from_iter(sg.iter_direct_children(second_child).unwrap().cloned()),
-            vec!["First Child",]
-        );
+        assert_eq!(sg.replace(NodeIndex::Root, "New Root"), Some("Root"));
+        assert_eq!(sg.root, "New Root");
+
+        assert_eq!(sg.replace(child, "New Child"), Some("Child"));
+        assert_eq!(sg[child], "New Child");
+        assert_eq!(sg.parent(grandchild), Some(child));
+        assert_eq!(get_values(&sg), vec!["New Child", "Grandchild"]);
+
+        sg.remove(child);
+        assert_eq!(sg.replace(child, "Gone"), None);
+        assert_eq!(sg.replace(grandchild, "Gone"), None);
     }
 
     #[test]
-    fn clear_works() {
-        let input_node: Vec<_> = (0..50_000).map(|v| format!("Node_{}", v)).collect();
+    fn to_contiguous_reconstructs() {
         let mut sg = SceneGraph::new("Root");
+        let first_child = sg.attach_at_root("First Child");
+        sg.attach(first_child, "First Grandchild").unwrap();
+        let second_grandchild = sg.attach(first_child, "Second Grandchild").unwrap();
+        sg.attach(second_grandchild, "First Greatgrandchild").unwrap();
+        sg.attach_at_root("Second Child");
 
-        for v in input_node.iter() {
-            sg.attach_at_root(v);
-        }
+        let (values, parent_indices) = sg.to_contiguous();
 
-        sg.clear();
+        assert_eq!(values[0], "Root");
+        assert_eq!(&values[1..], get_values(&sg));
+        assert_eq!(parent_indices, vec![None, Some(0), Some(1), Some(1), Some(3), Some(0)]);
 
-        assert_eq!(sg.len(), 0);
-        assert!(sg.is_empty());
-        assert!(sg.root_children.is_none());
-        assert!(sg.arena.is_empty());
+        // rebuild the graph purely from the parent array
+        let mut rebuilt = SceneGraph::new(values[0]);
+        let mut new_indices = vec![NodeIndex::Root];
+        for (value, parent) in values.iter().zip(parent_indices.iter()).skip(1) {
+            let new_idx = rebuilt.attach(new_indices[parent.unwrap()], *value).unwrap();
+            new_indices.push(new_idx);
+        }
+
+        assert_eq!(get_values(&rebuilt), get_values(&sg));
+        assert_eq!(rebuilt.to_contiguous(), (values, parent_indices));
     }
 }
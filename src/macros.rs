@@ -0,0 +1,89 @@
+/// Builds a [SceneGraph] from a nested description of its values, attaching every node in order.
+///
+/// Each node is a value expression, optionally followed by `=>` and a braced, comma separated
+/// list of its children, which may nest as deeply as needed.
+///
+/// ```
+/// use scene_graph::scene_graph;
+///
+/// let sg = scene_graph!("Root" => {
+///     "A" => { "A1", "A2" },
+///     "B",
+/// });
+///
+/// assert_eq!(Vec::from_iter(sg.iter().map(|(_parent, value)| *value)), ["A", "A1", "A2", "B"]);
+/// ```
+///
+/// [SceneGraph]: crate::SceneGraph
+#[macro_export]
+macro_rules! scene_graph {
+    // the internal arms come first, so that `@children` is never mistaken for a root value
+    (@children $sg:ident, $parent:expr; ) => {};
+    (@children $sg:ident, $parent:expr; $value:expr => { $($children:tt)* } $(, $($rest:tt)*)?) => {
+        let idx = $sg.attach($parent, $value).unwrap();
+        $crate::scene_graph!(@children $sg, idx; $($children)*);
+        $crate::scene_graph!(@children $sg, $parent; $($($rest)*)?);
+    };
+    (@children $sg:ident, $parent:expr; $value:expr $(, $($rest:tt)*)?) => {
+        $sg.attach($parent, $value).unwrap();
+        $crate::scene_graph!(@children $sg, $parent; $($($rest)*)?);
+    };
+    ($root:expr => { $($children:tt)* }) => {{
+        // an empty list of children never needs the `mut`
+        #[allow(unused_mut)]
+        let mut sg = $crate::SceneGraph::new($root);
+        $crate::scene_graph!(@children sg, $crate::NodeIndex::Root; $($children)*);
+        sg
+    }};
+    ($root:expr) => {
+        $crate::SceneGraph::new($root)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NodeIndex, SceneGraph};
+
+    #[test]
+    fn empty_graph() {
+        let sg: SceneGraph<&str> = crate::scene_graph!("Root");
+
+        assert!(sg.is_empty());
+        assert_eq!(sg.root, "Root");
+
+        let sg: SceneGraph<&str> = crate::scene_graph!("Root" => {});
+        assert!(sg.is_empty());
+    }
+
+    #[test]
+    fn matches_manual_construction() {
+        let built = crate::scene_graph!("Root" => {
+            "A" => {
+                "A1",
+                "A2" => { "A2a" => { "A2a1" } },
+            },
+            "B",
+            "C" => { "C1" },
+        });
+
+        let mut sg = SceneGraph::new("Root");
+        let a = sg.attach(NodeIndex::Root, "A").unwrap();
+        sg.attach(a, "A1").unwrap();
+        let a2 = sg.attach(a, "A2").unwrap();
+        let a2a = sg.attach(a2, "A2a").unwrap();
+        sg.attach(a2a, "A2a1").unwrap();
+        sg.attach(NodeIndex::Root, "B").unwrap();
+        let c = sg.attach(NodeIndex::Root, "C").unwrap();
+        sg.attach(c, "C1").unwrap();
+
+        assert_eq!(built.to_contiguous(), sg.to_contiguous());
+    }
+
+    #[test]
+    fn arbitrary_expressions() {
+        let base = 10;
+        let sg = crate::scene_graph!(base * 2 => { base + 1 => { base - 1 }, { base } });
+
+        assert_eq!(sg.to_contiguous(), (vec![20, 11, 9, 10], vec![None, Some(0), Some(1), Some(0)]));
+    }
+}
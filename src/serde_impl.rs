@@ -0,0 +1,152 @@
+use serde::{de::Error, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{NodeIndex, SceneGraph};
+
+/// A SceneGraph is written out as a flat list of `{ value, child_count }` nodes, starting with the
+/// root, in a depth first traversal. No arena indices ever leave the graph, and since nothing is
+/// nested, graphs of any depth can be written and read back.
+impl<T: Serialize> Serialize for SceneGraph<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let root = FlatNodeRef {
+            value: &self.root,
+            child_count: self.root_children.map_or(0, |v| v.count),
+        };
+        let descendants = self.iter_detailed().map(|visit| FlatNodeRef {
+            value: visit.value,
+            child_count: self.get(visit.index).unwrap().children.map_or(0, |v| v.count),
+        });
+
+        serializer.collect_seq(std::iter::once(root).chain(descendants))
+    }
+}
+
+/// Reading a SceneGraph back attaches every node afresh, so all of its indices are new.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SceneGraph<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut nodes = Vec::<FlatNode<T>>::deserialize(deserializer)?.into_iter();
+        let root = nodes.next().ok_or_else(|| D::Error::custom("a scene graph needs a root"))?;
+
+        let mut sg = SceneGraph::with_capacity(root.value, nodes.len());
+
+        // every node we're beneath, along with how many children it's still waiting on
+        let mut stack = vec![(NodeIndex::Root, root.child_count)];
+        for node in nodes {
+            while stack.last().is_some_and(|&(_, remaining)| remaining == 0) {
+                stack.pop();
+            }
+            let Some((parent, remaining)) = stack.last_mut() else {
+                return Err(D::Error::custom("more nodes than the child counts allow"));
+            };
+            *remaining -= 1;
+
+            let new_idx = sg.attach(*parent, node.value).expect("parent was just attached");
+            stack.push((new_idx, node.child_count));
+        }
+
+        if stack.iter().any(|&(_, remaining)| remaining != 0) {
+            return Err(D::Error::custom("fewer nodes than the child counts call for"));
+        }
+
+        Ok(sg)
+    }
+}
+
+struct FlatNodeRef<'a, T> {
+    value: &'a T,
+    child_count: usize,
+}
+
+impl<T: Serialize> Serialize for FlatNodeRef<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut node = serializer.serialize_struct("Node", 2)?;
+        node.serialize_field("value", self.value)?;
+        node.serialize_field("child_count", &self.child_count)?;
+        node.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Node")]
+struct FlatNode<T> {
+    value: T,
+    #[serde(default)]
+    child_count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_structure<T: Clone>(sg: &SceneGraph<T>) -> Vec<(usize, usize, T)> {
+        Vec::from_iter(sg.iter_detailed().map(|visit| (visit.depth, visit.sibling_index, visit.value.clone())))
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut sg = SceneGraph::new("Root".to_string());
+        let first_child = sg.attach_at_root("First Child".to_string());
+        let grandchild = sg.attach(first_child, "First Grandchild".to_string()).unwrap();
+        sg.attach(grandchild, "First Greatgrandchild".to_string()).unwrap();
+        sg.attach(first_child, "Second Grandchild".to_string()).unwrap();
+        sg.attach_at_root("Second Child".to_string());
+
+        let json = serde_json::to_string(&sg).unwrap();
+        let round_tripped: SceneGraph<String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(round_tripped.root, sg.root);
+        assert_eq!(round_tripped.len(), sg.len());
+        assert_eq!(get_structure(&round_tripped), get_structure(&sg));
+    }
+
+    #[test]
+    fn round_trips_a_deep_chain() {
+        let mut sg = SceneGraph::new(0);
+        let mut parent = NodeIndex::Root;
+        for depth in 1..=10_000 {
+            parent = sg.attach(parent, depth).unwrap();
+        }
+
+        let json = serde_json::to_string(&sg).unwrap();
+        let round_tripped: SceneGraph<i32> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(round_tripped.len(), 10_000);
+        assert_eq!(get_structure(&round_tripped), get_structure(&sg));
+        assert_eq!(round_tripped.deepest_leaf().map(|(_, depth)| depth), Some(10_000));
+    }
+
+    #[test]
+    fn writes_a_flat_list() {
+        let mut sg = SceneGraph::new(0);
+        let child = sg.attach_at_root(1);
+        sg.attach(child, 2).unwrap();
+        sg.attach_at_root(3);
+
+        assert_eq!(
+            serde_json::to_value(&sg).unwrap(),
+            serde_json::json!([
+                { "value": 0, "child_count": 2 },
+                { "value": 1, "child_count": 1 },
+                { "value": 2, "child_count": 0 },
+                { "value": 3, "child_count": 0 },
+            ])
+        );
+    }
+
+    #[test]
+    fn child_count_can_be_left_out() {
+        let json = r#"[{ "value": 0, "child_count": 1 }, { "value": 1 }]"#;
+        let sg: SceneGraph<i32> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(sg.root, 0);
+        assert_eq!(Vec::from_iter(sg.values().copied()), vec![1]);
+    }
+
+    #[test]
+    fn rejects_mismatched_child_counts() {
+        let parse = |json: &str| serde_json::from_str::<SceneGraph<i32>>(json);
+
+        assert!(parse("[]").is_err());
+        assert!(parse(r#"[{ "value": 0 }, { "value": 1 }]"#).is_err());
+        assert!(parse(r#"[{ "value": 0, "child_count": 2 }, { "value": 1 }]"#).is_err());
+    }
+}